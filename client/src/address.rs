@@ -0,0 +1,238 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Encode and decode CKB addresses.
+
+use std::convert::TryFrom;
+
+use bech32::{FromBase32 as _, ToBase32 as _};
+
+use crate::{
+    core::types::{
+        bytes::Bytes,
+        core::ScriptHashType,
+        fixed::{h256, H256},
+        packed,
+        prelude::*,
+    },
+    error::{Error, Result},
+};
+
+const PAYLOAD_SHORT: u8 = 0x01;
+const PAYLOAD_FULL_DATA: u8 = 0x02;
+const PAYLOAD_FULL_TYPE: u8 = 0x04;
+
+const CODE_HASH_INDEX_SIGHASH: u8 = 0x00;
+const CODE_HASH_INDEX_MULTISIG: u8 = 0x01;
+
+const SIGHASH_TYPE_HASH: H256 =
+    h256!("0x9bd7e06f3ecf4be0f2fcd2188b23f1b9fcc88e5d4b65a8637b17723bbda3cce8");
+const MULTISIG_TYPE_HASH: H256 =
+    h256!("0x5c5069eb0857efc65e1bca0c07df34c31663b3622fd3876c876320fc9634e2a8");
+
+const BLAKE160_LEN: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+}
+
+impl Network {
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::Mainnet => "ckb",
+            Self::Testnet => "ckt",
+        }
+    }
+
+    pub fn from_prefix(prefix: &str) -> Option<Self> {
+        match prefix {
+            "ckb" => Some(Self::Mainnet),
+            "ckt" => Some(Self::Testnet),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes a short or full payload address into its lock script.
+pub fn script_from_address(address: &str) -> Result<packed::Script> {
+    decode(address).map(|(_, script)| script)
+}
+
+/// Encodes a lock script as an address.
+///
+/// The short payload format is used when the script is a default secp256k1
+/// sighash or multisig lock, otherwise the full payload format is used.
+pub fn address_from_script(script: &packed::Script, network: Network) -> Result<String> {
+    let code_hash: H256 = script.code_hash().unpack();
+    let hash_type = ScriptHashType::try_from(script.hash_type()).map_err(Error::address)?;
+    let args = script.args().raw_data();
+    let code_hash_index = match hash_type {
+        ScriptHashType::Type if args.len() == BLAKE160_LEN => {
+            if code_hash == SIGHASH_TYPE_HASH {
+                Some(CODE_HASH_INDEX_SIGHASH)
+            } else if code_hash == MULTISIG_TYPE_HASH {
+                Some(CODE_HASH_INDEX_MULTISIG)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+    let mut payload = Vec::with_capacity(1 + 32 + args.len());
+    if let Some(index) = code_hash_index {
+        payload.push(PAYLOAD_SHORT);
+        payload.push(index);
+    } else {
+        let format = match hash_type {
+            ScriptHashType::Data => PAYLOAD_FULL_DATA,
+            ScriptHashType::Type => PAYLOAD_FULL_TYPE,
+        };
+        payload.push(format);
+        payload.extend_from_slice(code_hash.as_bytes());
+    }
+    payload.extend_from_slice(&args);
+    bech32::encode(network.prefix(), payload.to_base32()).map_err(Error::address)
+}
+
+/// Decodes an address into its network and lock script.
+pub fn decode(address: &str) -> Result<(Network, packed::Script)> {
+    let (prefix, data) = bech32::decode(address).map_err(Error::address)?;
+    let network = Network::from_prefix(&prefix)
+        .ok_or_else(|| Error::address(format!("unknown prefix \"{}\"", prefix)))?;
+    let payload = Vec::<u8>::from_base32(&data).map_err(Error::address)?;
+    let (code_hash, hash_type, args) = match payload.split_first() {
+        Some((&PAYLOAD_SHORT, rest)) => {
+            if rest.len() != 1 + BLAKE160_LEN {
+                return Err(Error::address("invalid short payload length"));
+            }
+            let code_hash = match rest[0] {
+                CODE_HASH_INDEX_SIGHASH => SIGHASH_TYPE_HASH,
+                CODE_HASH_INDEX_MULTISIG => MULTISIG_TYPE_HASH,
+                index => {
+                    return Err(Error::address(format!(
+                        "unknown code hash index {:#04x}",
+                        index
+                    )))
+                }
+            };
+            (code_hash, ScriptHashType::Type, &rest[1..])
+        }
+        Some((&format, rest)) if format == PAYLOAD_FULL_DATA || format == PAYLOAD_FULL_TYPE => {
+            if rest.len() < 32 {
+                return Err(Error::address("invalid full payload length"));
+            }
+            let code_hash = H256::from_slice(&rest[..32]).map_err(Error::address)?;
+            let hash_type = if format == PAYLOAD_FULL_DATA {
+                ScriptHashType::Data
+            } else {
+                ScriptHashType::Type
+            };
+            (code_hash, hash_type, &rest[32..])
+        }
+        Some((format, _)) => {
+            return Err(Error::address(format!(
+                "unknown payload format {:#04x}",
+                format
+            )))
+        }
+        None => return Err(Error::address("empty payload")),
+    };
+    let script = packed::Script::new_builder()
+        .code_hash(code_hash.pack())
+        .hash_type(hash_type.into())
+        .args(Bytes::from(args.to_vec()).pack())
+        .build();
+    Ok((network, script))
+}
+
+#[cfg(test)]
+mod tests {
+    use bech32::ToBase32 as _;
+
+    use super::*;
+
+    const ARGS: &str = "b39bbc0b3673c7d36450bc14cfcdad2d559c6c64";
+    const MAINNET_ADDRESS: &str = "ckb1qyqt8xaupvm8837nv3gtc9x0ekkj64vud3jqfwyw5v";
+    const TESTNET_ADDRESS: &str = "ckt1qyqt8xaupvm8837nv3gtc9x0ekkj64vud3jq5t63cs";
+
+    fn hex(input: &str) -> Vec<u8> {
+        (0..input.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&input[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn script(code_hash: &H256, hash_type: ScriptHashType, args: Vec<u8>) -> packed::Script {
+        packed::Script::new_builder()
+            .code_hash(code_hash.pack())
+            .hash_type(hash_type.into())
+            .args(Bytes::from(args).pack())
+            .build()
+    }
+
+    #[test]
+    fn short_payload_sighash() {
+        let lock = script(&SIGHASH_TYPE_HASH, ScriptHashType::Type, hex(ARGS));
+        for &(network, address) in &[
+            (Network::Mainnet, MAINNET_ADDRESS),
+            (Network::Testnet, TESTNET_ADDRESS),
+        ] {
+            assert_eq!(address_from_script(&lock, network).unwrap(), address);
+            let (decoded_network, decoded) = decode(address).unwrap();
+            assert_eq!(decoded_network, network);
+            assert_eq!(decoded.as_slice(), lock.as_slice());
+            let decoded = script_from_address(address).unwrap();
+            assert_eq!(decoded.as_slice(), lock.as_slice());
+        }
+    }
+
+    #[test]
+    fn short_payload_multisig() {
+        let lock = script(&MULTISIG_TYPE_HASH, ScriptHashType::Type, hex(ARGS));
+        let address = address_from_script(&lock, Network::Mainnet).unwrap();
+        assert!(address.starts_with("ckb1qyq"), "{}", address);
+        let decoded = script_from_address(&address).unwrap();
+        assert_eq!(decoded.as_slice(), lock.as_slice());
+    }
+
+    #[test]
+    fn full_payload() {
+        let code_hash = H256([0x11; 32]);
+        for &hash_type in &[ScriptHashType::Data, ScriptHashType::Type] {
+            let lock = script(&code_hash, hash_type, hex(ARGS));
+            let address = address_from_script(&lock, Network::Testnet).unwrap();
+            let (network, decoded) = decode(&address).unwrap();
+            assert_eq!(network, Network::Testnet);
+            assert_eq!(decoded.as_slice(), lock.as_slice());
+        }
+        // a default lock with other args can not use the short payload
+        let lock = script(&SIGHASH_TYPE_HASH, ScriptHashType::Type, vec![0; 8]);
+        let address = address_from_script(&lock, Network::Mainnet).unwrap();
+        let decoded = script_from_address(&address).unwrap();
+        assert_eq!(decoded.as_slice(), lock.as_slice());
+    }
+
+    #[test]
+    fn bad_prefix() {
+        let mut payload = vec![PAYLOAD_SHORT, CODE_HASH_INDEX_SIGHASH];
+        payload.extend(hex(ARGS));
+        let address = bech32::encode("xyz", payload.to_base32()).unwrap();
+        let err = script_from_address(&address).unwrap_err();
+        assert!(matches!(err, Error::Address(_)), "{}", err);
+    }
+
+    #[test]
+    fn bad_checksum() {
+        let mut address = MAINNET_ADDRESS.to_owned();
+        address.pop();
+        address.push('q');
+        assert!(script_from_address(&address).is_err());
+    }
+}
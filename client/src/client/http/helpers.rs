@@ -0,0 +1,1171 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{
+    collections::{HashMap, HashSet},
+    iter, thread,
+    time::{Duration, Instant},
+};
+
+use futures::{stream, StreamExt as _, TryStreamExt as _};
+use uckb_jsonrpc_core::types::{bytes::Bytes, core, fixed, packed, prelude::*, rpc};
+
+use crate::{
+    error::{Error, Result},
+    AsyncClient, Client,
+};
+
+const SECP256K1_SIGNATURE_SIZE: usize = 65;
+
+#[derive(Debug, Clone)]
+pub struct SystemCells {
+    pub secp256k1_sighash_all: packed::CellDep,
+    pub secp256k1_multisig_all: packed::CellDep,
+    pub dao: packed::CellDep,
+}
+
+#[derive(Debug)]
+pub struct CellsSnapshot {
+    pub tip_block_number: core::BlockNumber,
+    pub cells: Vec<rpc::CellOutputWithOutPoint>,
+    pub count: usize,
+    pub total_capacity: core::Capacity,
+}
+
+fn system_cell_dep(
+    tx: &core::TransactionView,
+    index: usize,
+    dep_type: core::DepType,
+) -> Result<packed::CellDep> {
+    if index >= tx.outputs().len() {
+        let msg = format!("output {} of genesis transaction {:#x}", index, tx.hash());
+        return Err(Error::not_found(msg));
+    }
+    let out_point = packed::OutPoint::new(tx.hash(), index as u32);
+    let cell_dep = packed::CellDep::new_builder()
+        .out_point(out_point)
+        .dep_type(dep_type.into())
+        .build();
+    Ok(cell_dep)
+}
+
+impl Client {
+    pub fn get_genesis_block_hash(&self) -> Result<fixed::H256> {
+        self.get_block_hash(0)?
+            .ok_or_else(|| Error::not_found("the genesis block"))
+    }
+
+    pub fn get_cells_by_lock_hash_paged(
+        &self,
+        lock_hash: fixed::H256,
+        from: core::BlockNumber,
+        to: core::BlockNumber,
+        page_size: u64,
+    ) -> Result<Vec<rpc::CellOutputWithOutPoint>> {
+        self.iter_cells_by_lock_hash(lock_hash, from, to, page_size)?
+            .try_fold(Vec::new(), |mut cells, page| {
+                cells.extend(page?);
+                Ok(cells)
+            })
+    }
+
+    pub fn get_cells_snapshot(
+        &self,
+        lock_hash: fixed::H256,
+        page_size: u64,
+    ) -> Result<CellsSnapshot> {
+        let tip_block_number = self.get_tip_block_number()?;
+        let cells = self.get_cells_by_lock_hash_paged(lock_hash, 0, tip_block_number, page_size)?;
+        let total_capacity = cells
+            .iter()
+            .try_fold(core::Capacity::zero(), |total, cell| {
+                total.safe_add(core::Capacity::shannons(cell.capacity.value()))
+            })
+            .map_err(Error::overflow)?;
+        Ok(CellsSnapshot {
+            tip_block_number,
+            count: cells.len(),
+            cells,
+            total_capacity,
+        })
+    }
+
+    pub fn iter_cells_by_lock_hash(
+        &self,
+        lock_hash: fixed::H256,
+        from: core::BlockNumber,
+        to: core::BlockNumber,
+        page_size: u64,
+    ) -> Result<impl Iterator<Item = Result<Vec<rpc::CellOutputWithOutPoint>>> + '_> {
+        if page_size == 0 {
+            return Err(Error::invalid_argument("page size should not be zero"));
+        }
+        let mut next = Some(from);
+        let pages = iter::from_fn(move || {
+            let start = next.filter(|start| *start <= to)?;
+            let end = start.saturating_add(page_size - 1).min(to);
+            next = end.checked_add(1);
+            log::trace!("fetch cells by lock hash in [{}, {}]", start, end);
+            Some(self.get_cells_by_lock_hash(lock_hash.clone(), start, end))
+        });
+        Ok(pages)
+    }
+
+    pub fn get_live_cell_output(
+        &self,
+        out_point: packed::OutPoint,
+    ) -> Result<Option<packed::CellOutput>> {
+        let cell = self.get_live_cell(out_point, false)?;
+        if cell.status != "live" {
+            log::trace!("cell is {}", cell.status);
+            return Ok(None);
+        }
+        Ok(cell.cell.map(|cell| cell.output.into()))
+    }
+
+    pub fn is_on_main_chain(&self, block_hash: fixed::H256) -> Result<bool> {
+        let block_number = if let Some(header) = self.get_header(block_hash.clone(), None)? {
+            header.number()
+        } else {
+            return Ok(false);
+        };
+        let main_hash = self.get_block_hash(block_number)?;
+        Ok(main_hash.as_ref() == Some(&block_hash))
+    }
+
+    pub fn iter_blocks_from(
+        &self,
+        start: core::BlockNumber,
+        poll_interval: Duration,
+    ) -> impl Iterator<Item = Result<core::BlockView>> + '_ {
+        let mut next = start;
+        let mut tip = None;
+        iter::from_fn(move || loop {
+            if tip.map(|tip| next > tip).unwrap_or(true) {
+                let number = match self.get_tip_block_number() {
+                    Ok(number) => number,
+                    Err(err) => return Some(Err(err)),
+                };
+                tip = Some(number);
+                if next > number {
+                    log::trace!("wait for block {} since tip is {}", next, number);
+                    thread::sleep(poll_interval);
+                    continue;
+                }
+            }
+            match self.get_block_by_number(next, None) {
+                Ok(Some(block)) => {
+                    next += 1;
+                    return Some(Ok(block));
+                }
+                Ok(None) => {
+                    log::trace!("block {} is gone, maybe the chain is reorganized", next);
+                    tip = None;
+                    thread::sleep(poll_interval);
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        })
+    }
+
+    pub fn resolve_inputs(&self, tx: &packed::Transaction) -> Result<Vec<packed::CellOutput>> {
+        let mut txs = HashMap::new();
+        tx.raw()
+            .inputs()
+            .into_iter()
+            .map(|input| {
+                let out_point = input.previous_output();
+                let tx_hash: fixed::H256 = out_point.tx_hash().unpack();
+                let index: u32 = out_point.index().unpack();
+                if !txs.contains_key(&tx_hash) {
+                    let tx = self
+                        .get_transaction(tx_hash.clone())?
+                        .ok_or_else(|| Error::not_found(format!("transaction {:#x}", tx_hash)))?
+                        .transaction;
+                    txs.insert(tx_hash.clone(), tx);
+                }
+                txs[&tx_hash]
+                    .inner
+                    .outputs
+                    .get(index as usize)
+                    .cloned()
+                    .map(Into::into)
+                    .ok_or_else(|| {
+                        let msg = format!("output {} of transaction {:#x}", index, tx_hash);
+                        Error::not_found(msg)
+                    })
+            })
+            .collect()
+    }
+
+    pub fn get_system_cells(&self) -> Result<SystemCells> {
+        if let Some(ref cells) = *self.system_cells.read() {
+            return Ok(cells.clone());
+        }
+        log::trace!("load system cells from the genesis block");
+        let genesis = self
+            .get_block_by_number(0, None)?
+            .ok_or_else(|| Error::not_found("the genesis block"))?;
+        let txs = genesis.transactions();
+        let (cellbase, dep_groups) = match (txs.first(), txs.get(1)) {
+            (Some(cellbase), Some(dep_groups)) => (cellbase, dep_groups),
+            _ => {
+                let msg = "the second transaction of the genesis block";
+                return Err(Error::not_found(msg));
+            }
+        };
+        let cells = SystemCells {
+            secp256k1_sighash_all: system_cell_dep(dep_groups, 0, core::DepType::DepGroup)?,
+            secp256k1_multisig_all: system_cell_dep(dep_groups, 1, core::DepType::DepGroup)?,
+            dao: system_cell_dep(cellbase, 2, core::DepType::Code)?,
+        };
+        *self.system_cells.write() = Some(cells.clone());
+        Ok(cells)
+    }
+
+    /// Estimates the fee of a transaction at a fee rate in shannons per KB.
+    pub fn estimate_fee(
+        tx: &packed::Transaction,
+        fee_rate: core::FeeRate,
+    ) -> Result<core::Capacity> {
+        let size = tx.serialized_size_in_block();
+        // `FeeRate::fee` saturates silently
+        if fee_rate.as_u64().checked_mul(size as u64).is_none() {
+            let msg = format!("fee of {} bytes at fee rate {}", size, fee_rate);
+            return Err(Error::overflow(msg));
+        }
+        Ok(fee_rate.fee(size))
+    }
+
+    pub fn complete_transaction(
+        &self,
+        tx: packed::Transaction,
+        fee_rate: core::FeeRate,
+    ) -> Result<core::TransactionView> {
+        let cell_dep = self.get_system_cells()?.secp256k1_sighash_all;
+        let mut builder = tx.as_advanced_builder();
+        if !tx
+            .raw()
+            .cell_deps()
+            .into_iter()
+            .any(|dep| dep.as_slice() == cell_dep.as_slice())
+        {
+            builder = builder.cell_dep(cell_dep);
+        }
+        if tx.witnesses().is_empty() {
+            // reserve a signature in the first witness of each lock group
+            let lock = Bytes::from(vec![0u8; SECP256K1_SIGNATURE_SIZE]).pack();
+            let placeholder = packed::WitnessArgs::new_builder()
+                .lock(packed::BytesOpt::new_builder().set(Some(lock)).build())
+                .build()
+                .as_bytes()
+                .pack();
+            let mut lock_hashes = HashSet::new();
+            let witnesses = self
+                .resolve_inputs(&tx)?
+                .into_iter()
+                .map(|cell| {
+                    let lock_hash: fixed::H256 = cell.lock().calc_script_hash().unpack();
+                    if lock_hashes.insert(lock_hash) {
+                        placeholder.clone()
+                    } else {
+                        packed::Bytes::default()
+                    }
+                })
+                .collect();
+            builder = builder.set_witnesses(witnesses);
+        }
+        let tx = builder.build();
+        let fee = Self::estimate_fee(&tx.data(), fee_rate)?;
+        let index = tx
+            .outputs()
+            .len()
+            .checked_sub(1)
+            .ok_or_else(|| Error::invalid_argument("transaction has no outputs"))?;
+        let (output, data) = tx
+            .output_with_data(index)
+            .ok_or_else(|| Error::invalid_argument("data of the last output is not found"))?;
+        let capacity: core::Capacity = output.capacity().unpack();
+        let capacity = capacity.safe_sub(fee).map_err(|_| {
+            let msg = format!(
+                "capacity of the last output is not enough to pay fee {}",
+                fee
+            );
+            Error::invalid_argument(msg)
+        })?;
+        let output = output.as_builder().capacity(capacity.pack()).build();
+        let data_capacity = core::Capacity::bytes(data.len()).map_err(Error::overflow)?;
+        if output
+            .is_lack_of_capacity(data_capacity)
+            .map_err(Error::overflow)?
+        {
+            let msg = "capacity of the last output is lower than its occupied capacity";
+            return Err(Error::invalid_argument(msg));
+        }
+        let mut outputs = tx.outputs().into_iter().collect::<Vec<_>>();
+        outputs[index] = output;
+        Ok(tx.as_advanced_builder().set_outputs(outputs).build())
+    }
+
+    pub fn wait_for_commit(
+        &self,
+        tx_hash: fixed::H256,
+        timeout: Duration,
+        poll_interval: Duration,
+    ) -> Result<rpc::TransactionWithStatus> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(tx) = self.get_transaction(tx_hash.clone())? {
+                if tx.tx_status.status == rpc::Status::Committed {
+                    return Ok(tx);
+                }
+                log::trace!("transaction {:#x} is {:?}", tx_hash, tx.tx_status.status);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                let msg = format!(
+                    "transaction {:#x} is not committed in {:?}",
+                    tx_hash, timeout
+                );
+                return Err(Error::timeout(msg));
+            }
+            thread::sleep(poll_interval.min(deadline - now));
+        }
+    }
+
+    pub fn send_and_wait(
+        &self,
+        tx: packed::Transaction,
+        outputs_validator: Option<rpc::OutputsValidator>,
+        timeout: Duration,
+        poll_interval: Duration,
+    ) -> Result<(fixed::H256, rpc::TransactionWithStatus)> {
+        let tx_hash = self.send_transaction(tx, outputs_validator)?;
+        log::trace!("transaction {:#x} is sent, wait for it committed", tx_hash);
+        let tx = self.wait_for_commit(tx_hash.clone(), timeout, poll_interval)?;
+        Ok((tx_hash, tx))
+    }
+
+    pub fn get_epoch_headers(
+        &self,
+        epoch_number: core::EpochNumber,
+    ) -> Result<Vec<core::HeaderView>> {
+        let epoch = self
+            .get_epoch_by_number(epoch_number)?
+            .ok_or_else(|| Error::not_found(format!("epoch {}", epoch_number)))?;
+        let start = epoch.start_number.value();
+        let end = start + epoch.length.value();
+        self.get_headers_by_number((start..end).collect(), None)?
+            .into_iter()
+            .zip(start..end)
+            .map(|(header_opt, block_number)| {
+                header_opt.ok_or_else(|| Error::not_found(format!("header {}", block_number)))
+            })
+            .collect()
+    }
+
+    pub fn is_synced(&self) -> Result<bool> {
+        self.sync_state().map(|state| !state.ibd)
+    }
+
+    pub fn get_node_addresses(&self) -> Result<Vec<String>> {
+        self.local_node_info().map(|node| {
+            node.addresses
+                .into_iter()
+                .map(|address| address.address)
+                .collect()
+        })
+    }
+
+    pub fn add_nodes<I>(&self, nodes: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let failures = nodes
+            .into_iter()
+            .filter_map(|(peer_id, address)| {
+                self.add_node(peer_id.clone(), address.clone())
+                    .err()
+                    .map(|err| (format!("add node {} ({})", peer_id, address), err))
+            })
+            .collect::<Vec<_>>();
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Failures(failures))
+        }
+    }
+
+    pub fn ping(&self) -> Result<Duration> {
+        let start = Instant::now();
+        self.get_tip_block_number()?;
+        Ok(start.elapsed())
+    }
+
+    pub fn get_node_version(&self) -> Result<String> {
+        self.local_node_info().map(|node| node.version)
+    }
+
+    pub fn get_node_id(&self) -> Result<String> {
+        self.local_node_info().map(|node| node.node_id)
+    }
+}
+
+impl AsyncClient {
+    pub async fn get_blocks_by_range(
+        &self,
+        from: core::BlockNumber,
+        to: core::BlockNumber,
+        concurrency: usize,
+    ) -> Result<Vec<core::BlockView>> {
+        if concurrency == 0 {
+            return Err(Error::invalid_argument("concurrency should not be zero"));
+        }
+        stream::iter(from..=to)
+            .map(|block_number| async move {
+                self.get_block_by_number(block_number, None)
+                    .await?
+                    .ok_or_else(|| Error::not_found(format!("block {}", block_number)))
+            })
+            .buffered(concurrency)
+            .try_collect()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use jsonrpc_core::{serde_json, Value};
+    use uckb_jsonrpc_core::types::{bytes::Bytes, core, fixed, packed, prelude::*, rpc};
+
+    use crate::{
+        error::Error,
+        testing::{client, MockServer, MockServerBuilder},
+        Client,
+    };
+
+    fn cell(capacity: u64) -> rpc::CellOutputWithOutPoint {
+        rpc::CellOutputWithOutPoint {
+            out_point: packed::OutPoint::default().into(),
+            block_hash: fixed::H256::default(),
+            capacity: capacity.into(),
+            lock: packed::Script::default().into(),
+            type_: None,
+            output_data_len: 0u64.into(),
+            cellbase: false,
+        }
+    }
+
+    fn pending(tx: &core::TransactionView) -> Value {
+        serde_json::to_value(rpc::TransactionWithStatus::with_pending(tx.clone())).unwrap()
+    }
+
+    fn committed(tx: &core::TransactionView) -> Value {
+        let tx = rpc::TransactionWithStatus::with_committed(tx.clone(), fixed::H256::default());
+        serde_json::to_value(tx).unwrap()
+    }
+
+    // one cell per block, each holds as many shannons as its block number
+    fn cells_by_block_number(server: MockServerBuilder) -> MockServerBuilder {
+        server.respond_with("get_cells_by_lock_hash", |params| {
+            let (_, from, to) =
+                params.parse::<(fixed::H256, rpc::BlockNumber, rpc::BlockNumber)>()?;
+            let cells = (from.value()..=to.value()).map(cell).collect::<Vec<_>>();
+            Ok(serde_json::to_value(cells).unwrap())
+        })
+    }
+
+    fn page_params(server: &MockServer) -> Vec<(u64, u64)> {
+        server
+            .requests()
+            .into_iter()
+            .filter(|(method, _)| method == "get_cells_by_lock_hash")
+            .map(|(_, params)| {
+                let (_, from, to) = params
+                    .parse::<(fixed::H256, rpc::BlockNumber, rpc::BlockNumber)>()
+                    .unwrap();
+                (from.value(), to.value())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn get_cells_by_lock_hash_in_pages() {
+        let server = cells_by_block_number(MockServerBuilder::new())
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let cells = client
+            .get_cells_by_lock_hash_paged(fixed::H256::default(), 3, 12, 4)
+            .unwrap();
+        let capacities = cells
+            .iter()
+            .map(|cell| cell.capacity.value())
+            .collect::<Vec<_>>();
+        assert_eq!(capacities, (3..=12).collect::<Vec<_>>());
+        assert_eq!(page_params(&server), vec![(3, 6), (7, 10), (11, 12)]);
+    }
+
+    #[test]
+    fn get_cells_snapshot_in_pages() {
+        let server = cells_by_block_number(MockServerBuilder::new())
+            .respond("get_tip_block_number", Value::String("0x9".to_owned()))
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let snapshot = client
+            .get_cells_snapshot(fixed::H256::default(), 3)
+            .unwrap();
+        assert_eq!(snapshot.tip_block_number, 9);
+        assert_eq!(snapshot.count, 10);
+        assert_eq!(snapshot.cells.len(), snapshot.count);
+        assert_eq!(snapshot.total_capacity, core::Capacity::shannons(45));
+        assert_eq!(page_params(&server), vec![(0, 2), (3, 5), (6, 8), (9, 9)]);
+    }
+
+    #[test]
+    fn map_each_live_cell_status() {
+        let output = packed::CellOutput::new_builder()
+            .capacity(100u64.pack())
+            .build();
+        let status = |status: &str, cell: Option<rpc::CellInfo>| {
+            let cell = rpc::CellWithStatus {
+                cell,
+                status: status.to_owned(),
+            };
+            Ok(serde_json::to_value(cell).unwrap())
+        };
+        let info = rpc::CellInfo {
+            output: output.clone().into(),
+            data: None,
+        };
+        let server = MockServerBuilder::new()
+            .respond_seq(
+                "get_live_cell",
+                vec![
+                    status("live", Some(info)),
+                    status("dead", None),
+                    status("unknown", None),
+                ],
+            )
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let out_point = packed::OutPoint::default();
+        let live = client.get_live_cell_output(out_point.clone()).unwrap();
+        assert_eq!(live.unwrap().as_slice(), output.as_slice());
+        assert!(client
+            .get_live_cell_output(out_point.clone())
+            .unwrap()
+            .is_none());
+        assert!(client.get_live_cell_output(out_point).unwrap().is_none());
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn is_on_main_chain_after_reorg() {
+        let header = core::HeaderBuilder::default().number(5u64.pack()).build();
+        let hash: fixed::H256 = header.hash().unpack();
+        let server = MockServerBuilder::new()
+            .respond(
+                "get_header",
+                serde_json::to_value(rpc::HeaderView::from(header)).unwrap(),
+            )
+            .respond_seq(
+                "get_block_hash",
+                vec![
+                    Ok(serde_json::to_value(&hash).unwrap()),
+                    Ok(serde_json::to_value(fixed::H256([1; 32])).unwrap()),
+                ],
+            )
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        assert!(client.is_on_main_chain(hash.clone()).unwrap());
+        assert!(!client.is_on_main_chain(hash).unwrap());
+        let requests = server.requests();
+        assert_eq!(requests[1].0, "get_block_hash");
+        assert_eq!(
+            requests[1].1,
+            jsonrpc_core::Params::Array(vec![Value::String("0x5".to_owned())])
+        );
+    }
+
+    #[test]
+    fn unknown_block_is_not_on_main_chain() {
+        let server = MockServerBuilder::new()
+            .respond("get_header", Value::Null)
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        assert!(!client.is_on_main_chain(fixed::H256::default()).unwrap());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    fn block_by_number(server: MockServerBuilder) -> MockServerBuilder {
+        server.respond_with("get_block_by_number", |params| {
+            let number = params.parse::<(rpc::BlockNumber, Option<rpc::Uint32>)>()?.0;
+            let header = core::HeaderBuilder::default()
+                .number(number.value().pack())
+                .build();
+            let block = core::BlockBuilder::default().header(header).build();
+            Ok(serde_json::to_value(rpc::BlockView::from(block)).unwrap())
+        })
+    }
+
+    #[test]
+    fn iter_blocks_and_wait_at_tip() {
+        let tips = ["0x2", "0x2", "0x4"]
+            .iter()
+            .map(|tip| Ok(Value::String((*tip).to_owned())))
+            .collect();
+        let server = block_by_number(MockServerBuilder::new())
+            .respond_seq("get_tip_block_number", tips)
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let numbers = client
+            .iter_blocks_from(1, Duration::from_millis(10))
+            .take(4)
+            .map(|block| block.unwrap().number())
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, vec![1, 2, 3, 4]);
+        let methods = server
+            .requests()
+            .into_iter()
+            .map(|(method, _)| method)
+            .collect::<Vec<_>>();
+        let (tip, block) = ("get_tip_block_number", "get_block_by_number");
+        assert_eq!(methods, vec![tip, block, block, tip, tip, block, block]);
+    }
+
+    fn source_tx(capacities: &[u64]) -> core::TransactionView {
+        let outputs = capacities
+            .iter()
+            .map(|capacity| {
+                packed::CellOutput::new_builder()
+                    .capacity(capacity.pack())
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        core::TransactionBuilder::default()
+            .outputs_data(vec![packed::Bytes::default(); outputs.len()])
+            .outputs(outputs)
+            .build()
+    }
+
+    #[test]
+    fn resolve_inputs_from_two_transactions() {
+        let first = source_tx(&[1, 2, 3]);
+        let second = source_tx(&[10, 20]);
+        let txs = [first.clone(), second.clone()];
+        let server = MockServerBuilder::new()
+            .respond_with("get_transaction", move |params| {
+                let hash = params.parse::<(fixed::H256,)>()?.0;
+                let tx = txs.iter().find(|tx| tx.hash() == hash.pack()).unwrap();
+                Ok(committed(tx))
+            })
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let inputs = vec![(&second, 1), (&first, 2), (&first, 0), (&second, 0)]
+            .into_iter()
+            .map(|(tx, index)| packed::CellInput::new(packed::OutPoint::new(tx.hash(), index), 0))
+            .collect::<Vec<_>>();
+        let tx = core::TransactionBuilder::default()
+            .inputs(inputs)
+            .build()
+            .data();
+        let capacities = client
+            .resolve_inputs(&tx)
+            .unwrap()
+            .into_iter()
+            .map(|output| Unpack::<core::Capacity>::unpack(&output.capacity()).as_u64())
+            .collect::<Vec<_>>();
+        assert_eq!(capacities, vec![20, 3, 1, 10]);
+        // each source transaction is fetched once
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn get_blocks_by_range_in_order() {
+        let server = block_by_number(MockServerBuilder::new())
+            .delay(Duration::from_millis(10))
+            .start()
+            .unwrap();
+        let (rt, client) = client(server.url());
+        let client = client.to_async().unwrap();
+        let blocks = rt.block_on(client.get_blocks_by_range(5, 24, 4)).unwrap();
+        assert_eq!(blocks.len(), 20);
+        let numbers = blocks.iter().map(|b| b.number()).collect::<Vec<_>>();
+        assert_eq!(numbers, (5..=24).collect::<Vec<_>>());
+        assert_eq!(server.requests().len(), 20);
+    }
+
+    #[test]
+    fn missing_block_is_not_found() {
+        let server = MockServerBuilder::new()
+            .respond("get_block_by_number", Value::Null)
+            .start()
+            .unwrap();
+        let (rt, client) = client(server.url());
+        let client = client.to_async().unwrap();
+        let err = rt
+            .block_on(client.get_blocks_by_range(1, 2, 2))
+            .unwrap_err();
+        assert!(matches!(err, Error::NotFound(_)), "{}", err);
+        assert_eq!(err.to_string(), "not found: block 1");
+    }
+
+    #[test]
+    fn overflowing_capacity_sum_is_overflow() {
+        let cells = vec![cell(u64::MAX), cell(1)];
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x0".to_owned()))
+            .respond(
+                "get_cells_by_lock_hash",
+                serde_json::to_value(cells).unwrap(),
+            )
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let err = client
+            .get_cells_snapshot(fixed::H256::default(), 10)
+            .unwrap_err();
+        assert!(matches!(err, Error::Overflow(_)), "{}", err);
+    }
+
+    #[test]
+    fn invalid_arguments_are_local() {
+        let server = MockServerBuilder::new().start().unwrap();
+        let (_rt, client) = client(server.url());
+        let err = client
+            .get_cells_by_lock_hash_paged(fixed::H256::default(), 0, 10, 0)
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidArgument(_)), "{}", err);
+        let err = client
+            .set_ban(String::new(), "foo".to_owned(), None, None, None)
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidArgument(_)), "{}", err);
+        assert_eq!(server.hits(), 0);
+    }
+
+    #[test]
+    fn wait_for_commit_polls_until_committed() {
+        let tx = core::TransactionBuilder::default().build();
+        let server = MockServerBuilder::new()
+            .respond_seq(
+                "get_transaction",
+                vec![Ok(pending(&tx)), Ok(pending(&tx)), Ok(committed(&tx))],
+            )
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let tx_with_status = client
+            .wait_for_commit(
+                tx.hash().unpack(),
+                Duration::from_secs(5),
+                Duration::from_millis(10),
+            )
+            .unwrap();
+        assert_eq!(tx_with_status.tx_status.status, rpc::Status::Committed);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn wait_for_commit_through_each_status() {
+        let tx = core::TransactionBuilder::default().build();
+        let proposed = rpc::TransactionWithStatus::with_proposed(tx.clone());
+        let server = MockServerBuilder::new()
+            .respond_seq(
+                "get_transaction",
+                vec![
+                    Ok(Value::Null),
+                    Ok(pending(&tx)),
+                    Ok(serde_json::to_value(proposed).unwrap()),
+                    Ok(committed(&tx)),
+                ],
+            )
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let tx_with_status = client
+            .wait_for_commit(
+                tx.hash().unpack(),
+                Duration::from_secs(5),
+                Duration::from_millis(10),
+            )
+            .unwrap();
+        assert_eq!(tx_with_status.tx_status.status, rpc::Status::Committed);
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    #[test]
+    fn wait_for_commit_times_out() {
+        let tx = core::TransactionBuilder::default().build();
+        let server = MockServerBuilder::new()
+            .respond("get_transaction", pending(&tx))
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let err = client
+            .wait_for_commit(
+                tx.hash().unpack(),
+                Duration::from_millis(100),
+                Duration::from_millis(20),
+            )
+            .unwrap_err();
+        assert!(matches!(err, Error::Timeout(_)), "{}", err);
+    }
+
+    #[test]
+    fn send_and_wait_surfaces_rejection() {
+        let tx = core::TransactionBuilder::default().build();
+        let rejection = jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::ServerError(-3),
+            message: "rejected".to_owned(),
+            data: None,
+        };
+        let server = MockServerBuilder::new()
+            .respond_seq("send_transaction", vec![Err(rejection)])
+            .respond("get_transaction", committed(&tx))
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let err = client
+            .send_and_wait(
+                tx.data(),
+                None,
+                Duration::from_secs(5),
+                Duration::from_millis(10),
+            )
+            .unwrap_err();
+        match err {
+            Error::RpcError(jsonrpc_core_client::RpcError::JsonRpcError(err)) => {
+                assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(-3));
+                assert_eq!(err.message, "rejected");
+            }
+            err => panic!("unexpected error {}", err),
+        }
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn send_and_wait_until_committed() {
+        let tx = core::TransactionBuilder::default().build();
+        let tx_hash: fixed::H256 = tx.hash().unpack();
+        let server = MockServerBuilder::new()
+            .respond("send_transaction", serde_json::to_value(&tx_hash).unwrap())
+            .respond_seq(
+                "get_transaction",
+                vec![Ok(pending(&tx)), Ok(committed(&tx))],
+            )
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let (hash, tx_with_status) = client
+            .send_and_wait(
+                tx.data(),
+                None,
+                Duration::from_secs(5),
+                Duration::from_millis(10),
+            )
+            .unwrap();
+        assert_eq!(hash, tx_hash);
+        assert_eq!(tx_with_status.tx_status.status, rpc::Status::Committed);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn is_synced_follows_ibd() {
+        let state = |ibd: bool| {
+            let state = rpc::SyncState {
+                ibd,
+                best_known_block_number: 0x400.into(),
+                best_known_block_timestamp: 0x5cd2_b117.into(),
+                orphan_blocks_count: 0x2.into(),
+                inflight_blocks_count: 0x3.into(),
+                ..Default::default()
+            };
+            Ok(serde_json::to_value(state).unwrap())
+        };
+        let server = MockServerBuilder::new()
+            .respond_seq("sync_state", vec![state(true), state(false)])
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        assert!(!client.is_synced().unwrap());
+        assert!(client.is_synced().unwrap());
+        let state = client.sync_state().unwrap();
+        assert_eq!(state.best_known_block_number.value(), 0x400);
+        assert_eq!(state.best_known_block_timestamp.value(), 0x5cd2_b117);
+        assert_eq!(state.orphan_blocks_count.value(), 2);
+        assert_eq!(state.inflight_blocks_count.value(), 3);
+    }
+
+    #[test]
+    fn add_nodes_reports_failed_peers() {
+        let server = MockServerBuilder::new()
+            .respond_with("add_node", |params| match params {
+                jsonrpc_core::Params::Array(ref args) if args[0] == "bad" => {
+                    Err(jsonrpc_core::Error::invalid_params("unreachable"))
+                }
+                _ => Ok(Value::Null),
+            })
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let nodes = vec![
+            ("good".to_owned(), "/ip4/127.0.0.1/tcp/8115".to_owned()),
+            ("bad".to_owned(), "/ip4/127.0.0.1/tcp/8116".to_owned()),
+            ("fine".to_owned(), "/ip4/127.0.0.1/tcp/8117".to_owned()),
+        ];
+        match client.add_nodes(nodes).unwrap_err() {
+            Error::Failures(failures) => {
+                assert_eq!(failures.len(), 1);
+                assert!(failures[0].0.contains("bad"), "{}", failures[0].0);
+            }
+            err => panic!("unexpected error {}", err),
+        }
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn estimate_fee_by_size() {
+        let tx = core::TransactionBuilder::default()
+            .output(packed::CellOutput::default())
+            .output_data(Default::default())
+            .build()
+            .data();
+        let size = tx.serialized_size_in_block() as u64;
+        let fee = Client::estimate_fee(&tx, core::FeeRate::from_u64(1000)).unwrap();
+        assert_eq!(fee, core::Capacity::shannons(size));
+        let fee = Client::estimate_fee(&tx, core::FeeRate::from_u64(3000)).unwrap();
+        assert_eq!(fee, core::Capacity::shannons(size * 3));
+        let err = Client::estimate_fee(&tx, core::FeeRate::from_u64(u64::MAX)).unwrap_err();
+        assert!(matches!(err, Error::Overflow(_)), "{}", err);
+    }
+
+    fn genesis() -> core::BlockView {
+        let tx = |count: usize| {
+            core::TransactionBuilder::default()
+                .outputs(vec![packed::CellOutput::default(); count])
+                .outputs_data(vec![packed::Bytes::default(); count])
+                .build()
+        };
+        core::BlockBuilder::default()
+            .transaction(tx(3))
+            .transaction(tx(2))
+            .build()
+    }
+
+    fn lock(arg: u8) -> packed::Script {
+        packed::Script::new_builder()
+            .args(Bytes::from(vec![arg; 20]).pack())
+            .build()
+    }
+
+    fn complete(
+        capacity: core::Capacity,
+    ) -> (
+        Vec<packed::Bytes>,
+        core::BlockView,
+        crate::error::Result<core::TransactionView>,
+    ) {
+        let genesis = genesis();
+        let prev = core::TransactionBuilder::default()
+            .outputs(vec![
+                packed::CellOutput::new_builder().lock(lock(1)).build(),
+                packed::CellOutput::new_builder().lock(lock(2)).build(),
+                packed::CellOutput::new_builder().lock(lock(1)).build(),
+            ])
+            .outputs_data(vec![packed::Bytes::default(); 3])
+            .build();
+        let inputs = (0..3)
+            .map(|index| packed::CellInput::new(packed::OutPoint::new(prev.hash(), index), 0))
+            .collect::<Vec<_>>();
+        let tx = core::TransactionBuilder::default()
+            .inputs(inputs)
+            .output(
+                packed::CellOutput::new_builder()
+                    .capacity(capacity.pack())
+                    .build(),
+            )
+            .output_data(packed::Bytes::default())
+            .build();
+        let server = MockServerBuilder::new()
+            .respond(
+                "get_block_by_number",
+                serde_json::to_value(rpc::BlockView::from(genesis.clone())).unwrap(),
+            )
+            .respond("get_transaction", committed(&prev))
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let completed = client.complete_transaction(tx.data(), core::FeeRate::from_u64(1000));
+        let placeholder = packed::WitnessArgs::new_builder()
+            .lock(
+                packed::BytesOpt::new_builder()
+                    .set(Some(Bytes::from(vec![0u8; 65]).pack()))
+                    .build(),
+            )
+            .build()
+            .as_bytes()
+            .pack();
+        let witnesses = vec![placeholder.clone(), placeholder, packed::Bytes::default()];
+        (witnesses, genesis, completed)
+    }
+
+    #[test]
+    fn cache_system_cells() {
+        let genesis = genesis();
+        let server = MockServerBuilder::new()
+            .respond(
+                "get_block_by_number",
+                serde_json::to_value(rpc::BlockView::from(genesis.clone())).unwrap(),
+            )
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let cells = client.get_system_cells().unwrap();
+        assert_eq!(server.requests().len(), 1);
+        let cached = client.get_system_cells().unwrap();
+        assert_eq!(server.requests().len(), 1);
+        let txs = genesis.transactions();
+        let expected = [
+            (&cells.secp256k1_sighash_all, txs[1].hash(), 0),
+            (&cells.secp256k1_multisig_all, txs[1].hash(), 1),
+            (&cells.dao, txs[0].hash(), 2),
+        ];
+        for (cell_dep, tx_hash, index) in expected.iter() {
+            let out_point = packed::OutPoint::new(tx_hash.clone(), *index);
+            assert_eq!(cell_dep.out_point().as_slice(), out_point.as_slice());
+        }
+        assert_eq!(cached.dao.as_slice(), cells.dao.as_slice());
+    }
+
+    #[test]
+    fn complete_transaction_adds_deps_and_pays_fee() {
+        let capacity = core::Capacity::bytes(1000).unwrap();
+        let (witnesses, genesis, completed) = complete(capacity);
+        let tx = completed.unwrap();
+        let sighash_dep = packed::CellDep::new_builder()
+            .out_point(packed::OutPoint::new(genesis.transactions()[1].hash(), 0))
+            .dep_type(core::DepType::DepGroup.into())
+            .build();
+        let cell_deps = tx.cell_deps().into_iter().collect::<Vec<_>>();
+        assert_eq!(cell_deps.len(), 1);
+        assert_eq!(cell_deps[0].as_slice(), sighash_dep.as_slice());
+        let actual = tx.witnesses().into_iter().collect::<Vec<_>>();
+        assert_eq!(actual.len(), witnesses.len());
+        for (actual, expected) in actual.iter().zip(witnesses.iter()) {
+            assert_eq!(actual.as_slice(), expected.as_slice());
+        }
+        let size = tx.data().serialized_size_in_block() as u64;
+        let paid: core::Capacity = tx.outputs().get(0).unwrap().capacity().unpack();
+        assert_eq!(paid, core::Capacity::shannons(capacity.as_u64() - size));
+        assert_eq!(tx.hash(), tx.data().calc_tx_hash());
+    }
+
+    #[test]
+    fn complete_transaction_with_insufficient_capacity() {
+        let (_, _, completed) = complete(core::Capacity::shannons(10));
+        let err = completed.unwrap_err();
+        assert!(matches!(err, Error::InvalidArgument(_)), "{}", err);
+        let occupied = packed::CellOutput::default()
+            .occupied_capacity(core::Capacity::zero())
+            .unwrap();
+        let (_, _, completed) = complete(occupied);
+        let err = completed.unwrap_err();
+        assert!(matches!(err, Error::InvalidArgument(_)), "{}", err);
+    }
+
+    #[test]
+    fn get_epoch_headers_and_retry_each() {
+        let epoch = rpc::EpochView {
+            number: 1.into(),
+            start_number: 10.into(),
+            length: 20.into(),
+            compact_target: 0.into(),
+        };
+        let server = MockServerBuilder::new()
+            .respond("get_epoch_by_number", serde_json::to_value(epoch).unwrap())
+            .respond_with("get_header_by_number", |params| {
+                let number = params.parse::<(rpc::BlockNumber, Option<rpc::Uint32>)>()?.0;
+                let header = core::HeaderBuilder::default()
+                    .number(number.value().pack())
+                    .build();
+                Ok(serde_json::to_value(rpc::HeaderView::from(header)).unwrap())
+            })
+            .fail_requests(2)
+            .start()
+            .unwrap();
+        let (_rt, mut client) = client(server.url());
+        client.set_http_retry(3, Duration::from_millis(10)).unwrap();
+        let headers = client.get_epoch_headers(1).unwrap();
+        let numbers = headers.iter().map(|h| h.number()).collect::<Vec<_>>();
+        assert_eq!(numbers, (10..30).collect::<Vec<_>>());
+        // the epoch and each header are requested once, only the failed requests are sent again
+        assert_eq!(server.requests().len(), 21);
+        assert_eq!(server.hits(), 23);
+    }
+
+    fn local_node() -> Value {
+        serde_json::json!({
+            "version": "0.38.0 (1b7a4ff 2020-11-03)",
+            "node_id": "QmTRHCdrRtgUzYLNCin69zEvPvLYdxUZLLfLYyHVY3DZAS",
+            "active": true,
+            "addresses": [
+                { "address": "/ip4/192.168.0.2/tcp/8112", "score": "0xff" }
+            ],
+            "protocols": [
+                { "id": "0x0", "name": "/ckb/ping", "support_versions": ["0.0.1"] }
+            ],
+            "connections": "0xb"
+        })
+    }
+
+    #[test]
+    fn get_node_version_and_id() {
+        let server = MockServerBuilder::new()
+            .respond("local_node_info", local_node())
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        assert_eq!(
+            client.get_node_version().unwrap(),
+            "0.38.0 (1b7a4ff 2020-11-03)"
+        );
+        assert_eq!(
+            client.get_node_id().unwrap(),
+            "QmTRHCdrRtgUzYLNCin69zEvPvLYdxUZLLfLYyHVY3DZAS"
+        );
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests
+            .iter()
+            .all(|(method, _)| method == "local_node_info"));
+    }
+
+    #[test]
+    fn ping_measures_a_round_trip() {
+        let delay = Duration::from_millis(50);
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x1".to_owned()))
+            .delay(delay)
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        assert!(client.ping().unwrap() >= delay);
+        assert_eq!(server.requests()[0].0, "get_tip_block_number");
+        server.close();
+        assert!(client.ping().is_err());
+    }
+}
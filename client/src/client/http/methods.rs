@@ -6,13 +6,19 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use jsonrpc_core::futures::Future;
-use uckb_jsonrpc_core::types::{core, fixed, packed, rpc};
+use std::any;
+
+use jsonrpc_core::{
+    futures::{future, stream, Future, Stream},
+    serde::{de::DeserializeOwned, Serialize},
+    serde_json, Value,
+};
+use uckb_jsonrpc_core::types::{core, fixed, packed, prelude::*, rpc};
 
 use super::HttpClient;
 use crate::{
     error::{Error, Result},
-    Client,
+    AsyncClient, Client,
 };
 
 // block on future
@@ -27,15 +33,58 @@ macro_rules! b {
     }};
 }
 
+// block on future, retry on transient errors
+macro_rules! br {
+    ($self:ident, $method:ident $( ,$param:ident )*) => {{
+        br!($self, $method, $( $param, )* )
+    }};
+    ($self:ident, $method:ident, $( $param:ident, )*) => {{
+        let rt = $self.runtime();
+        let http = $self.http()?;
+        http.retry(|| {
+            let fut = http.$method( $( Clone::clone(&$param), )* );
+            rt.block_on_01(fut)
+        })
+    }};
+}
+
+// await on future
+macro_rules! a {
+    ($self:ident, $method:ident $( ,$param:ident )*) => {{
+        a!($self, $method, $( $param, )* )
+    }};
+    ($self:ident, $method:ident, $( $param:ident, )*) => {{
+        let rt = $self.runtime();
+        let fut = $self.http().$method( $( $param, )* );
+        rt.run_01(fut).await
+    }};
+}
+
+// await on future, retry on transient errors
+macro_rules! ar {
+    ($self:ident, $method:ident $( ,$param:ident )*) => {{
+        ar!($self, $method, $( $param, )* )
+    }};
+    ($self:ident, $method:ident, $( $param:ident, )*) => {{
+        let rt = $self.runtime();
+        let http = $self.http();
+        http.retry_async(|| {
+            let fut = http.$method( $( Clone::clone(&$param), )* );
+            rt.run_01(fut)
+        })
+        .await
+    }};
+}
+
 // convert future
 macro_rules! c{
     ($self:ident, $method:ident $( ,$param:expr )*) => {{
         c!($self, $method, $( $param, )* )
     }};
     ($self:ident, $method:ident, $( $param:expr, )*) => {{
-        $self.client()
-            .$method( $( $param, )* )
-            .map_err(Into::into)
+        let fut = $self.client().$method( $( $param, )* );
+        let fut = $self.with_timeout(fut);
+        $self.instrument(stringify!($method), fut).map_err(Into::into)
     }};
 }
 
@@ -59,6 +108,35 @@ macro_rules! ci {
     }};
 }
 
+const INDEXER_PER_PAGE_LIMIT: u64 = 50;
+// the http transport sends at most 8 requests at the same time
+const BATCH_CONCURRENCY: usize = 8;
+
+fn check_per_page(per_page: u64) -> Result<()> {
+    if per_page > INDEXER_PER_PAGE_LIMIT {
+        let msg = format!(
+            "per_page should not be greater than {} but got {}",
+            INDEXER_PER_PAGE_LIMIT, per_page
+        );
+        Err(Error::invalid_argument(msg))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_ban_command(command: &str) -> Result<()> {
+    match command {
+        "insert" | "delete" => Ok(()),
+        _ => {
+            let msg = format!(
+                "ban command should be \"insert\" or \"delete\" but got \"{}\"",
+                command
+            );
+            Err(Error::invalid_argument(msg))
+        }
+    }
+}
+
 impl Client {
     // Module Chain
     pub fn get_block(
@@ -66,7 +144,7 @@ impl Client {
         block_hash: fixed::H256,
         verbosity: Option<u32>,
     ) -> Result<Option<core::BlockView>> {
-        b!(self, get_block, block_hash, verbosity)
+        br!(self, get_block, block_hash, verbosity)
     }
 
     pub fn get_block_by_number(
@@ -74,7 +152,19 @@ impl Client {
         block_number: core::BlockNumber,
         verbosity: Option<u32>,
     ) -> Result<Option<core::BlockView>> {
-        b!(self, get_block_by_number, block_number, verbosity)
+        br!(self, get_block_by_number, block_number, verbosity)
+    }
+
+    pub fn get_blocks_by_number(
+        &self,
+        block_numbers: Vec<core::BlockNumber>,
+        verbosity: Option<u32>,
+    ) -> Result<Vec<Option<core::BlockView>>> {
+        b!(self, get_blocks_by_number, block_numbers, verbosity)
+    }
+
+    pub fn get_packed_block(&self, block_hash: fixed::H256) -> Result<Option<packed::Block>> {
+        br!(self, get_packed_block, block_hash)
     }
 
     pub fn get_header(
@@ -82,7 +172,7 @@ impl Client {
         block_hash: fixed::H256,
         verbosity: Option<u32>,
     ) -> Result<Option<core::HeaderView>> {
-        b!(self, get_header, block_hash, verbosity)
+        br!(self, get_header, block_hash, verbosity)
     }
 
     pub fn get_header_by_number(
@@ -90,22 +180,39 @@ impl Client {
         block_number: core::BlockNumber,
         verbosity: Option<u32>,
     ) -> Result<Option<core::HeaderView>> {
-        b!(self, get_header_by_number, block_number, verbosity)
+        br!(self, get_header_by_number, block_number, verbosity)
+    }
+
+    pub fn get_headers_by_number(
+        &self,
+        block_numbers: Vec<core::BlockNumber>,
+        verbosity: Option<u32>,
+    ) -> Result<Vec<Option<core::HeaderView>>> {
+        b!(self, get_headers_by_number, block_numbers, verbosity)
     }
 
     pub fn get_transaction(
         &self,
         tx_hash: fixed::H256,
     ) -> Result<Option<rpc::TransactionWithStatus>> {
-        b!(self, get_transaction, tx_hash)
+        br!(self, get_transaction, tx_hash)
     }
 
     pub fn get_block_hash(&self, block_number: core::BlockNumber) -> Result<Option<fixed::H256>> {
-        b!(self, get_block_hash, block_number)
+        br!(self, get_block_hash, block_number)
     }
 
     pub fn get_tip_header(&self, verbosity: Option<u32>) -> Result<core::HeaderView> {
-        b!(self, get_tip_header, verbosity)
+        br!(self, get_tip_header, verbosity)
+    }
+
+    pub fn get_cells_by_lock_hash(
+        &self,
+        lock_hash: fixed::H256,
+        from: core::BlockNumber,
+        to: core::BlockNumber,
+    ) -> Result<Vec<rpc::CellOutputWithOutPoint>> {
+        br!(self, get_cells_by_lock_hash, lock_hash, from, to)
     }
 
     pub fn get_live_cell(
@@ -113,29 +220,36 @@ impl Client {
         out_point: packed::OutPoint,
         with_data: bool,
     ) -> Result<rpc::CellWithStatus> {
-        b!(self, get_live_cell, out_point, with_data)
+        br!(self, get_live_cell, out_point, with_data)
     }
 
     pub fn get_tip_block_number(&self) -> Result<core::BlockNumber> {
-        b!(self, get_tip_block_number)
+        br!(self, get_tip_block_number)
     }
 
     pub fn get_current_epoch(&self) -> Result<rpc::EpochView> {
-        b!(self, get_current_epoch)
+        br!(self, get_current_epoch)
     }
 
     pub fn get_epoch_by_number(
         &self,
         epoch_number: core::EpochNumber,
     ) -> Result<Option<rpc::EpochView>> {
-        b!(self, get_epoch_by_number, epoch_number)
+        br!(self, get_epoch_by_number, epoch_number)
     }
 
     pub fn get_block_economic_state(
         &self,
         block_hash: fixed::H256,
     ) -> Result<Option<rpc::BlockEconomicState>> {
-        b!(self, get_block_economic_state, block_hash)
+        br!(self, get_block_economic_state, block_hash)
+    }
+
+    pub fn get_cellbase_output_capacity_details(
+        &self,
+        block_hash: fixed::H256,
+    ) -> Result<Option<rpc::BlockReward>> {
+        br!(self, get_cellbase_output_capacity_details, block_hash)
     }
 
     pub fn get_transaction_proof(
@@ -143,14 +257,14 @@ impl Client {
         tx_hashes: Vec<fixed::H256>,
         block_hash: Option<fixed::H256>,
     ) -> Result<rpc::TransactionProof> {
-        b!(self, get_transaction_proof, tx_hashes, block_hash)
+        br!(self, get_transaction_proof, tx_hashes, block_hash)
     }
 
     pub fn verify_transaction_proof(
         &self,
         tx_proof: rpc::TransactionProof,
     ) -> Result<Vec<fixed::H256>> {
-        b!(self, verify_transaction_proof, tx_proof)
+        br!(self, verify_transaction_proof, tx_proof)
     }
 
     pub fn get_fork_block(
@@ -158,7 +272,11 @@ impl Client {
         block_hash: fixed::H256,
         verbosity: Option<u32>,
     ) -> Result<Option<rpc::BlockView>> {
-        b!(self, get_fork_block, block_hash, verbosity)
+        br!(self, get_fork_block, block_hash, verbosity)
+    }
+
+    pub fn get_block_median_time(&self, block_hash: fixed::H256) -> Result<Option<u64>> {
+        br!(self, get_block_median_time, block_hash)
     }
 
     //
@@ -174,7 +292,7 @@ impl Client {
     }
 
     pub fn tx_pool_info(&self) -> Result<rpc::TxPoolInfo> {
-        b!(self, tx_pool_info)
+        br!(self, tx_pool_info)
     }
 
     pub fn clear_tx_pool(&self) -> Result<()> {
@@ -191,7 +309,7 @@ impl Client {
         proposals_limit: Option<u64>,
         max_version: Option<core::Version>,
     ) -> Result<rpc::BlockTemplate> {
-        b!(
+        br!(
             self,
             get_block_template,
             bytes_limit,
@@ -209,7 +327,7 @@ impl Client {
     //
 
     pub fn get_blockchain_info(&self) -> Result<rpc::ChainInfo> {
-        b!(self, get_blockchain_info)
+        br!(self, get_blockchain_info)
     }
 
     //
@@ -217,15 +335,19 @@ impl Client {
     //
 
     pub fn local_node_info(&self) -> Result<rpc::LocalNode> {
-        b!(self, local_node_info)
+        br!(self, local_node_info)
     }
 
     pub fn get_peers(&self) -> Result<Vec<rpc::RemoteNode>> {
-        b!(self, get_peers)
+        br!(self, get_peers)
+    }
+
+    pub fn get_peers_state(&self) -> Result<Vec<rpc::PeerState>> {
+        br!(self, get_peers_state)
     }
 
     pub fn get_banned_addresses(&self) -> Result<Vec<rpc::BannedAddr>> {
-        b!(self, get_banned_addresses)
+        br!(self, get_banned_addresses)
     }
 
     pub fn clear_banned_addresses(&self) -> Result<()> {
@@ -240,11 +362,12 @@ impl Client {
         absolute: Option<bool>,
         reason: Option<String>,
     ) -> Result<()> {
+        check_ban_command(&command)?;
         b!(self, set_ban, address, command, ban_time, absolute, reason)
     }
 
     pub fn sync_state(&self) -> Result<rpc::SyncState> {
-        b!(self, sync_state)
+        br!(self, sync_state)
     }
 
     pub fn set_network_active(&self, state: bool) -> Result<()> {
@@ -276,7 +399,7 @@ impl Client {
     //
 
     pub fn dry_run_transaction(&self, tx: packed::Transaction) -> Result<rpc::DryRunResult> {
-        b!(self, dry_run_transaction, tx)
+        br!(self, dry_run_transaction, tx)
     }
 
     pub fn calculate_dao_maximum_withdraw(
@@ -284,7 +407,7 @@ impl Client {
         out_point: packed::OutPoint,
         block_hash: fixed::H256,
     ) -> Result<core::Capacity> {
-        b!(self, calculate_dao_maximum_withdraw, out_point, block_hash)
+        br!(self, calculate_dao_maximum_withdraw, out_point, block_hash)
     }
 
     //
@@ -307,6 +430,62 @@ impl Client {
         b!(self, set_extra_logger, name, config_opt)
     }
 
+    //
+    // Module Indexer
+    //
+
+    pub fn get_live_cells_by_lock_hash(
+        &self,
+        lock_hash: fixed::H256,
+        page: u64,
+        per_page: u64,
+        reverse_order: Option<bool>,
+    ) -> Result<Vec<rpc::LiveCell>> {
+        check_per_page(per_page)?;
+        br!(
+            self,
+            get_live_cells_by_lock_hash,
+            lock_hash,
+            page,
+            per_page,
+            reverse_order,
+        )
+    }
+
+    pub fn get_transactions_by_lock_hash(
+        &self,
+        lock_hash: fixed::H256,
+        page: u64,
+        per_page: u64,
+        reverse_order: Option<bool>,
+    ) -> Result<Vec<rpc::CellTransaction>> {
+        check_per_page(per_page)?;
+        br!(
+            self,
+            get_transactions_by_lock_hash,
+            lock_hash,
+            page,
+            per_page,
+            reverse_order,
+        )
+    }
+
+    pub fn get_lock_hash_index_states(&self) -> Result<Vec<rpc::LockHashIndexState>> {
+        br!(self, get_lock_hash_index_states)
+    }
+
+    pub fn index_lock_hash(
+        &self,
+        lock_hash: fixed::H256,
+        index_from: Option<core::BlockNumber>,
+    ) -> Result<rpc::LockHashIndexState> {
+        b!(self, index_lock_hash, lock_hash, index_from)
+    }
+
+    pub fn deindex_lock_hash(&self, lock_hash: fixed::H256) -> Result<()> {
+        b!(self, deindex_lock_hash, lock_hash)
+    }
+
     //
     // Module IntegrationTest
     //
@@ -343,6 +522,432 @@ impl Client {
     ) -> Result<fixed::H256> {
         b!(self, broadcast_transaction, transaction, cycles)
     }
+
+    //
+    // Raw
+    //
+
+    pub fn call<P, R>(&self, method: &str, params: P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned + Send + 'static,
+    {
+        let params = serde_json::to_value(params).map_err(Error::invalid_argument)?;
+        b!(self, call, method, params)
+    }
+
+    pub fn notify<P: Serialize>(&self, method: &str, params: P) -> Result<()> {
+        let params = serde_json::to_value(params).map_err(Error::invalid_argument)?;
+        b!(self, notify, method, params)
+    }
+}
+
+impl AsyncClient {
+    // Module Chain
+    pub async fn get_block(
+        &self,
+        block_hash: fixed::H256,
+        verbosity: Option<u32>,
+    ) -> Result<Option<core::BlockView>> {
+        ar!(self, get_block, block_hash, verbosity)
+    }
+
+    pub async fn get_block_by_number(
+        &self,
+        block_number: core::BlockNumber,
+        verbosity: Option<u32>,
+    ) -> Result<Option<core::BlockView>> {
+        ar!(self, get_block_by_number, block_number, verbosity)
+    }
+
+    pub async fn get_blocks_by_number(
+        &self,
+        block_numbers: Vec<core::BlockNumber>,
+        verbosity: Option<u32>,
+    ) -> Result<Vec<Option<core::BlockView>>> {
+        a!(self, get_blocks_by_number, block_numbers, verbosity)
+    }
+
+    pub async fn get_packed_block(&self, block_hash: fixed::H256) -> Result<Option<packed::Block>> {
+        ar!(self, get_packed_block, block_hash)
+    }
+
+    pub async fn get_header(
+        &self,
+        block_hash: fixed::H256,
+        verbosity: Option<u32>,
+    ) -> Result<Option<core::HeaderView>> {
+        ar!(self, get_header, block_hash, verbosity)
+    }
+
+    pub async fn get_header_by_number(
+        &self,
+        block_number: core::BlockNumber,
+        verbosity: Option<u32>,
+    ) -> Result<Option<core::HeaderView>> {
+        ar!(self, get_header_by_number, block_number, verbosity)
+    }
+
+    pub async fn get_headers_by_number(
+        &self,
+        block_numbers: Vec<core::BlockNumber>,
+        verbosity: Option<u32>,
+    ) -> Result<Vec<Option<core::HeaderView>>> {
+        a!(self, get_headers_by_number, block_numbers, verbosity)
+    }
+
+    pub async fn get_transaction(
+        &self,
+        tx_hash: fixed::H256,
+    ) -> Result<Option<rpc::TransactionWithStatus>> {
+        ar!(self, get_transaction, tx_hash)
+    }
+
+    pub async fn get_block_hash(
+        &self,
+        block_number: core::BlockNumber,
+    ) -> Result<Option<fixed::H256>> {
+        ar!(self, get_block_hash, block_number)
+    }
+
+    pub async fn get_tip_header(&self, verbosity: Option<u32>) -> Result<core::HeaderView> {
+        ar!(self, get_tip_header, verbosity)
+    }
+
+    pub async fn get_cells_by_lock_hash(
+        &self,
+        lock_hash: fixed::H256,
+        from: core::BlockNumber,
+        to: core::BlockNumber,
+    ) -> Result<Vec<rpc::CellOutputWithOutPoint>> {
+        ar!(self, get_cells_by_lock_hash, lock_hash, from, to)
+    }
+
+    pub async fn get_live_cell(
+        &self,
+        out_point: packed::OutPoint,
+        with_data: bool,
+    ) -> Result<rpc::CellWithStatus> {
+        ar!(self, get_live_cell, out_point, with_data)
+    }
+
+    pub async fn get_tip_block_number(&self) -> Result<core::BlockNumber> {
+        ar!(self, get_tip_block_number)
+    }
+
+    pub async fn get_current_epoch(&self) -> Result<rpc::EpochView> {
+        ar!(self, get_current_epoch)
+    }
+
+    pub async fn get_epoch_by_number(
+        &self,
+        epoch_number: core::EpochNumber,
+    ) -> Result<Option<rpc::EpochView>> {
+        ar!(self, get_epoch_by_number, epoch_number)
+    }
+
+    pub async fn get_block_economic_state(
+        &self,
+        block_hash: fixed::H256,
+    ) -> Result<Option<rpc::BlockEconomicState>> {
+        ar!(self, get_block_economic_state, block_hash)
+    }
+
+    pub async fn get_cellbase_output_capacity_details(
+        &self,
+        block_hash: fixed::H256,
+    ) -> Result<Option<rpc::BlockReward>> {
+        ar!(self, get_cellbase_output_capacity_details, block_hash)
+    }
+
+    pub async fn get_transaction_proof(
+        &self,
+        tx_hashes: Vec<fixed::H256>,
+        block_hash: Option<fixed::H256>,
+    ) -> Result<rpc::TransactionProof> {
+        ar!(self, get_transaction_proof, tx_hashes, block_hash)
+    }
+
+    pub async fn verify_transaction_proof(
+        &self,
+        tx_proof: rpc::TransactionProof,
+    ) -> Result<Vec<fixed::H256>> {
+        ar!(self, verify_transaction_proof, tx_proof)
+    }
+
+    pub async fn get_fork_block(
+        &self,
+        block_hash: fixed::H256,
+        verbosity: Option<u32>,
+    ) -> Result<Option<rpc::BlockView>> {
+        ar!(self, get_fork_block, block_hash, verbosity)
+    }
+
+    pub async fn get_block_median_time(&self, block_hash: fixed::H256) -> Result<Option<u64>> {
+        ar!(self, get_block_median_time, block_hash)
+    }
+
+    //
+    // Module Pool
+    //
+
+    pub async fn send_transaction(
+        &self,
+        tx: packed::Transaction,
+        outputs_validator: Option<rpc::OutputsValidator>,
+    ) -> Result<fixed::H256> {
+        a!(self, send_transaction, tx, outputs_validator)
+    }
+
+    pub async fn tx_pool_info(&self) -> Result<rpc::TxPoolInfo> {
+        ar!(self, tx_pool_info)
+    }
+
+    pub async fn clear_tx_pool(&self) -> Result<()> {
+        a!(self, clear_tx_pool)
+    }
+
+    //
+    // Module Miner
+    //
+
+    pub async fn get_block_template(
+        &self,
+        bytes_limit: Option<u64>,
+        proposals_limit: Option<u64>,
+        max_version: Option<core::Version>,
+    ) -> Result<rpc::BlockTemplate> {
+        ar!(
+            self,
+            get_block_template,
+            bytes_limit,
+            proposals_limit,
+            max_version,
+        )
+    }
+
+    pub async fn submit_block(&self, work_id: String, block: packed::Block) -> Result<fixed::H256> {
+        a!(self, submit_block, work_id, block)
+    }
+
+    //
+    // Module Stats
+    //
+
+    pub async fn get_blockchain_info(&self) -> Result<rpc::ChainInfo> {
+        ar!(self, get_blockchain_info)
+    }
+
+    //
+    // Module Net
+    //
+
+    pub async fn local_node_info(&self) -> Result<rpc::LocalNode> {
+        ar!(self, local_node_info)
+    }
+
+    pub async fn get_peers(&self) -> Result<Vec<rpc::RemoteNode>> {
+        ar!(self, get_peers)
+    }
+
+    pub async fn get_peers_state(&self) -> Result<Vec<rpc::PeerState>> {
+        ar!(self, get_peers_state)
+    }
+
+    pub async fn get_banned_addresses(&self) -> Result<Vec<rpc::BannedAddr>> {
+        ar!(self, get_banned_addresses)
+    }
+
+    pub async fn clear_banned_addresses(&self) -> Result<()> {
+        a!(self, clear_banned_addresses)
+    }
+
+    pub async fn set_ban(
+        &self,
+        address: String,
+        command: String,
+        ban_time: Option<rpc::Timestamp>,
+        absolute: Option<bool>,
+        reason: Option<String>,
+    ) -> Result<()> {
+        check_ban_command(&command)?;
+        a!(self, set_ban, address, command, ban_time, absolute, reason)
+    }
+
+    pub async fn sync_state(&self) -> Result<rpc::SyncState> {
+        ar!(self, sync_state)
+    }
+
+    pub async fn set_network_active(&self, state: bool) -> Result<()> {
+        a!(self, set_network_active, state)
+    }
+
+    pub async fn add_node(&self, peer_id: String, address: String) -> Result<()> {
+        a!(self, add_node, peer_id, address)
+    }
+
+    pub async fn remove_node(&self, peer_id: String) -> Result<()> {
+        a!(self, remove_node, peer_id)
+    }
+
+    pub async fn ping_peers(&self) -> Result<()> {
+        a!(self, ping_peers)
+    }
+
+    //
+    // Module Alert
+    //
+
+    pub async fn send_alert(&self, alert: rpc::Alert) -> Result<()> {
+        a!(self, send_alert, alert)
+    }
+
+    //
+    // Module Experiment
+    //
+
+    pub async fn dry_run_transaction(&self, tx: packed::Transaction) -> Result<rpc::DryRunResult> {
+        ar!(self, dry_run_transaction, tx)
+    }
+
+    pub async fn calculate_dao_maximum_withdraw(
+        &self,
+        out_point: packed::OutPoint,
+        block_hash: fixed::H256,
+    ) -> Result<core::Capacity> {
+        ar!(self, calculate_dao_maximum_withdraw, out_point, block_hash)
+    }
+
+    //
+    // Module Debug
+    //
+
+    pub async fn jemalloc_profiling_dump(&self) -> Result<String> {
+        a!(self, jemalloc_profiling_dump)
+    }
+
+    pub async fn update_main_logger(&self, config: rpc::MainLoggerConfig) -> Result<()> {
+        a!(self, update_main_logger, config)
+    }
+
+    pub async fn set_extra_logger(
+        &self,
+        name: String,
+        config_opt: Option<rpc::ExtraLoggerConfig>,
+    ) -> Result<()> {
+        a!(self, set_extra_logger, name, config_opt)
+    }
+
+    //
+    // Module Indexer
+    //
+
+    pub async fn get_live_cells_by_lock_hash(
+        &self,
+        lock_hash: fixed::H256,
+        page: u64,
+        per_page: u64,
+        reverse_order: Option<bool>,
+    ) -> Result<Vec<rpc::LiveCell>> {
+        check_per_page(per_page)?;
+        ar!(
+            self,
+            get_live_cells_by_lock_hash,
+            lock_hash,
+            page,
+            per_page,
+            reverse_order,
+        )
+    }
+
+    pub async fn get_transactions_by_lock_hash(
+        &self,
+        lock_hash: fixed::H256,
+        page: u64,
+        per_page: u64,
+        reverse_order: Option<bool>,
+    ) -> Result<Vec<rpc::CellTransaction>> {
+        check_per_page(per_page)?;
+        ar!(
+            self,
+            get_transactions_by_lock_hash,
+            lock_hash,
+            page,
+            per_page,
+            reverse_order,
+        )
+    }
+
+    pub async fn get_lock_hash_index_states(&self) -> Result<Vec<rpc::LockHashIndexState>> {
+        ar!(self, get_lock_hash_index_states)
+    }
+
+    pub async fn index_lock_hash(
+        &self,
+        lock_hash: fixed::H256,
+        index_from: Option<core::BlockNumber>,
+    ) -> Result<rpc::LockHashIndexState> {
+        a!(self, index_lock_hash, lock_hash, index_from)
+    }
+
+    pub async fn deindex_lock_hash(&self, lock_hash: fixed::H256) -> Result<()> {
+        a!(self, deindex_lock_hash, lock_hash)
+    }
+
+    //
+    // Module IntegrationTest
+    //
+
+    pub async fn process_block_without_verify(
+        &self,
+        data: packed::Block,
+        broadcast: bool,
+    ) -> Result<Option<fixed::H256>> {
+        a!(self, process_block_without_verify, data, broadcast)
+    }
+
+    pub async fn truncate(&self, target_tip_hash: fixed::H256) -> Result<()> {
+        a!(self, truncate, target_tip_hash)
+    }
+
+    pub async fn generate_block(
+        &self,
+        block_assembler_script: Option<packed::Script>,
+        block_assembler_message: Option<packed::Bytes>,
+    ) -> Result<fixed::H256> {
+        a!(
+            self,
+            generate_block,
+            block_assembler_script,
+            block_assembler_message
+        )
+    }
+
+    pub async fn broadcast_transaction(
+        &self,
+        transaction: packed::Transaction,
+        cycles: core::Cycle,
+    ) -> Result<fixed::H256> {
+        a!(self, broadcast_transaction, transaction, cycles)
+    }
+
+    //
+    // Raw
+    //
+
+    pub async fn call<P, R>(&self, method: &str, params: P) -> Result<R>
+    where
+        P: Serialize,
+        R: DeserializeOwned + Send + 'static,
+    {
+        let params = serde_json::to_value(params).map_err(Error::invalid_argument)?;
+        a!(self, call, method, params)
+    }
+
+    pub async fn notify<P: Serialize>(&self, method: &str, params: P) -> Result<()> {
+        let params = serde_json::to_value(params).map_err(Error::invalid_argument)?;
+        a!(self, notify, method, params)
+    }
 }
 
 impl HttpClient {
@@ -371,6 +976,38 @@ impl HttpClient {
         )
     }
 
+    fn get_blocks_by_number(
+        &self,
+        block_numbers: Vec<core::BlockNumber>,
+        verbosity: Option<u32>,
+    ) -> impl Future<Item = Vec<Option<core::BlockView>>, Error = Error> {
+        let client = self.clone();
+        stream::iter_ok(block_numbers)
+            .map(move |block_number| {
+                let http = client.clone();
+                client.retry_01(move || http.get_block_by_number(block_number, verbosity))
+            })
+            .buffered(BATCH_CONCURRENCY)
+            .collect()
+    }
+
+    fn get_packed_block(
+        &self,
+        block_hash: fixed::H256,
+    ) -> impl Future<Item = Option<packed::Block>, Error = Error> {
+        let client = self.clone();
+        future::result(
+            serde_json::to_value((block_hash, rpc::Uint32::from(0)))
+                .map_err(Error::invalid_argument),
+        )
+        .and_then(move |params| client.call::<Option<rpc::JsonBytes>>("get_block", params))
+        .and_then(|bytes_opt| {
+            bytes_opt
+                .map(|bytes| packed::Block::from_slice(bytes.as_bytes()).map_err(Error::decode))
+                .transpose()
+        })
+    }
+
     fn get_header(
         &self,
         block_hash: fixed::H256,
@@ -392,6 +1029,21 @@ impl HttpClient {
         )
     }
 
+    fn get_headers_by_number(
+        &self,
+        block_numbers: Vec<core::BlockNumber>,
+        verbosity: Option<u32>,
+    ) -> impl Future<Item = Vec<Option<core::HeaderView>>, Error = Error> {
+        let client = self.clone();
+        stream::iter_ok(block_numbers)
+            .map(move |block_number| {
+                let http = client.clone();
+                client.retry_01(move || http.get_header_by_number(block_number, verbosity))
+            })
+            .buffered(BATCH_CONCURRENCY)
+            .collect()
+    }
+
     fn get_transaction(
         &self,
         tx_hash: fixed::H256,
@@ -413,6 +1065,21 @@ impl HttpClient {
         ci!(self, get_tip_header, verbosity.map(Into::into))
     }
 
+    fn get_cells_by_lock_hash(
+        &self,
+        lock_hash: fixed::H256,
+        from: core::BlockNumber,
+        to: core::BlockNumber,
+    ) -> impl Future<Item = Vec<rpc::CellOutputWithOutPoint>, Error = Error> {
+        c!(
+            self,
+            get_cells_by_lock_hash,
+            lock_hash,
+            from.into(),
+            to.into()
+        )
+    }
+
     fn get_live_cell(
         &self,
         out_point: packed::OutPoint,
@@ -443,6 +1110,13 @@ impl HttpClient {
         c!(self, get_block_economic_state, block_hash)
     }
 
+    fn get_cellbase_output_capacity_details(
+        &self,
+        block_hash: fixed::H256,
+    ) -> impl Future<Item = Option<rpc::BlockReward>, Error = Error> {
+        c!(self, get_cellbase_output_capacity_details, block_hash)
+    }
+
     fn get_transaction_proof(
         &self,
         tx_hashes: Vec<fixed::H256>,
@@ -466,6 +1140,13 @@ impl HttpClient {
         coi!(self, get_fork_block, block_hash, verbosity.map(Into::into))
     }
 
+    fn get_block_median_time(
+        &self,
+        block_hash: fixed::H256,
+    ) -> impl Future<Item = Option<u64>, Error = Error> {
+        coi!(self, get_block_median_time, block_hash)
+    }
+
     //
     // Module Pool
     //
@@ -532,6 +1213,10 @@ impl HttpClient {
         c!(self, get_peers)
     }
 
+    fn get_peers_state(&self) -> impl Future<Item = Vec<rpc::PeerState>, Error = Error> {
+        c!(self, get_peers_state)
+    }
+
     fn get_banned_addresses(&self) -> impl Future<Item = Vec<rpc::BannedAddr>, Error = Error> {
         c!(self, get_banned_addresses)
     }
@@ -623,7 +1308,63 @@ impl HttpClient {
         name: String,
         config_opt: Option<rpc::ExtraLoggerConfig>,
     ) -> impl Future<Item = (), Error = Error> {
-        c!(self, set_extra_logger, name, config_opt.map(Into::into))
+        c!(self, set_extra_logger, name, config_opt)
+    }
+
+    //
+    // Module Indexer
+    //
+
+    fn get_live_cells_by_lock_hash(
+        &self,
+        lock_hash: fixed::H256,
+        page: u64,
+        per_page: u64,
+        reverse_order: Option<bool>,
+    ) -> impl Future<Item = Vec<rpc::LiveCell>, Error = Error> {
+        c!(
+            self,
+            get_live_cells_by_lock_hash,
+            lock_hash,
+            page.into(),
+            per_page.into(),
+            reverse_order
+        )
+    }
+
+    fn get_transactions_by_lock_hash(
+        &self,
+        lock_hash: fixed::H256,
+        page: u64,
+        per_page: u64,
+        reverse_order: Option<bool>,
+    ) -> impl Future<Item = Vec<rpc::CellTransaction>, Error = Error> {
+        c!(
+            self,
+            get_transactions_by_lock_hash,
+            lock_hash,
+            page.into(),
+            per_page.into(),
+            reverse_order
+        )
+    }
+
+    fn get_lock_hash_index_states(
+        &self,
+    ) -> impl Future<Item = Vec<rpc::LockHashIndexState>, Error = Error> {
+        c!(self, get_lock_hash_index_states)
+    }
+
+    fn index_lock_hash(
+        &self,
+        lock_hash: fixed::H256,
+        index_from: Option<core::BlockNumber>,
+    ) -> impl Future<Item = rpc::LockHashIndexState, Error = Error> {
+        c!(self, index_lock_hash, lock_hash, index_from.map(Into::into))
+    }
+
+    fn deindex_lock_hash(&self, lock_hash: fixed::H256) -> impl Future<Item = (), Error = Error> {
+        c!(self, deindex_lock_hash, lock_hash)
     }
 
     //
@@ -667,4 +1408,429 @@ impl HttpClient {
             cycles.into()
         )
     }
+
+    //
+    // Raw
+    //
+
+    fn call<R>(&self, method: &str, params: Value) -> impl Future<Item = R, Error = Error>
+    where
+        R: DeserializeOwned + 'static,
+    {
+        let fut = self
+            .raw()
+            .call_method(method, any::type_name::<R>(), params);
+        let fut = self.with_timeout(fut);
+        self.instrument(method.to_owned(), fut).map_err(Into::into)
+    }
+
+    fn notify(&self, method: &str, params: Value) -> impl Future<Item = (), Error = Error> {
+        let fut = self.raw().notify(method, params);
+        let fut = self.with_timeout(fut);
+        self.instrument(method.to_owned(), fut).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use jsonrpc_core::{serde_json, Params, Value};
+    use uckb_jsonrpc_core::types::{core, fixed, prelude::*, rpc};
+
+    use crate::testing::{client, MockServerBuilder};
+
+    #[test]
+    fn async_client_decodes_as_client() {
+        let header = core::HeaderBuilder::default().number(7u64.pack()).build();
+        let block = core::BlockBuilder::default().header(header.clone()).build();
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x7".to_owned()))
+            .respond(
+                "get_tip_header",
+                serde_json::to_value(rpc::HeaderView::from(header)).unwrap(),
+            )
+            .respond(
+                "get_block_by_number",
+                serde_json::to_value(rpc::BlockView::from(block)).unwrap(),
+            )
+            .start()
+            .unwrap();
+        let (rt, client) = client(server.url());
+        let async_client = client.to_async().unwrap();
+
+        let number = client.get_tip_block_number().unwrap();
+        let async_number = rt.block_on(async_client.get_tip_block_number()).unwrap();
+        assert_eq!(number, 7);
+        assert_eq!(number, async_number);
+
+        let header = client.get_tip_header(None).unwrap();
+        let async_header = rt.block_on(async_client.get_tip_header(None)).unwrap();
+        assert_eq!(header.number(), 7);
+        assert_eq!(header.hash(), async_header.hash());
+
+        let block = client.get_block_by_number(7, None).unwrap().unwrap();
+        let async_block = rt
+            .block_on(async_client.get_block_by_number(7, None))
+            .unwrap()
+            .unwrap();
+        assert_eq!(block.number(), 7);
+        assert_eq!(block.hash(), async_block.hash());
+        assert_eq!(server.requests().len(), 6);
+    }
+
+    #[test]
+    fn call_decodes_as_typed_methods() {
+        let header = core::HeaderBuilder::default().number(7u64.pack()).build();
+        let block = core::BlockBuilder::default().header(header.clone()).build();
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x7".to_owned()))
+            .respond(
+                "get_tip_header",
+                serde_json::to_value(rpc::HeaderView::from(header)).unwrap(),
+            )
+            .respond(
+                "get_block_by_number",
+                serde_json::to_value(rpc::BlockView::from(block)).unwrap(),
+            )
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+
+        let number: rpc::BlockNumber = client.call("get_tip_block_number", ()).unwrap();
+        assert_eq!(number.value(), client.get_tip_block_number().unwrap());
+
+        let header: rpc::HeaderView = client.call("get_tip_header", ()).unwrap();
+        let typed = client.get_tip_header(None).unwrap();
+        assert_eq!(header.hash.pack(), typed.hash());
+
+        let block: Option<rpc::BlockView> = client
+            .call(
+                "get_block_by_number",
+                (rpc::BlockNumber::from(7), Option::<rpc::Uint32>::None),
+            )
+            .unwrap();
+        let typed = client.get_block_by_number(7, None).unwrap().unwrap();
+        assert_eq!(block.unwrap().header.hash.pack(), typed.hash());
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 6);
+        assert_eq!(requests[4].1, requests[5].1);
+    }
+
+    #[test]
+    fn retry_packed_block() {
+        let block = core::BlockBuilder::default()
+            .header(core::HeaderBuilder::default().number(3u64.pack()).build())
+            .build();
+        let bytes = rpc::JsonBytes::from_bytes(block.data().as_bytes());
+        let server = MockServerBuilder::new()
+            .respond("get_block", serde_json::to_value(bytes).unwrap())
+            .fail_requests(1)
+            .start()
+            .unwrap();
+        let (rt, mut client) = client(server.url());
+        client.set_http_retry(2, Duration::from_millis(10)).unwrap();
+        let async_client = client.to_async().unwrap();
+
+        let packed = client.get_packed_block(block.hash().unpack()).unwrap();
+        assert_eq!(packed.unwrap().as_slice(), block.data().as_slice());
+        assert_eq!(server.hits(), 2);
+
+        let packed = rt
+            .block_on(async_client.get_packed_block(block.hash().unpack()))
+            .unwrap();
+        assert_eq!(packed.unwrap().as_slice(), block.data().as_slice());
+        let (method, params) = &server.requests()[1];
+        assert_eq!(method, "get_block");
+        assert_eq!(
+            serde_json::to_value(params).unwrap()[1],
+            Value::String("0x0".to_owned())
+        );
+    }
+
+    #[test]
+    fn get_blocks_by_number_in_order_and_retry_each() {
+        let server = MockServerBuilder::new()
+            .respond_with("get_block_by_number", |params| {
+                let number = params
+                    .parse::<(rpc::BlockNumber, Option<rpc::Uint32>)>()?
+                    .0
+                    .value();
+                if number % 2 == 0 {
+                    Ok(Value::Null)
+                } else {
+                    let header = core::HeaderBuilder::default().number(number.pack()).build();
+                    let block = core::BlockBuilder::default().header(header).build();
+                    Ok(serde_json::to_value(rpc::BlockView::from(block)).unwrap())
+                }
+            })
+            .fail_requests(1)
+            .start()
+            .unwrap();
+        let (_rt, mut client) = client(server.url());
+        client.set_http_retry(2, Duration::from_millis(10)).unwrap();
+        let numbers = vec![5, 2, 9, 1, 4, 7];
+        let blocks = client
+            .get_blocks_by_number(numbers.clone(), None)
+            .unwrap()
+            .into_iter()
+            .map(|block_opt| block_opt.map(|block| block.number()))
+            .collect::<Vec<_>>();
+        assert_eq!(blocks, vec![Some(5), None, Some(9), Some(1), None, Some(7)]);
+        // only the failed request is sent again
+        assert_eq!(server.requests().len(), numbers.len());
+        assert_eq!(server.hits(), numbers.len() + 1);
+    }
+
+    #[test]
+    fn decode_block_median_time() {
+        let server = MockServerBuilder::new()
+            .respond_seq(
+                "get_block_median_time",
+                vec![
+                    Ok(Value::String("0x16e3b5fa6b1".to_owned())),
+                    Ok(Value::Null),
+                ],
+            )
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let hash = fixed::H256([1; 32]);
+        let median_time = client.get_block_median_time(hash.clone()).unwrap();
+        assert_eq!(median_time, Some(1_572_954_154_673));
+        assert_eq!(client.get_block_median_time(hash.clone()).unwrap(), None);
+        let requests = server.requests();
+        assert_eq!(requests[0].0, "get_block_median_time");
+        let hash = serde_json::to_value(hash).unwrap();
+        assert_eq!(requests[0].1, Params::Array(vec![hash]));
+    }
+
+    #[test]
+    fn send_notification() {
+        let server = MockServerBuilder::new()
+            .accept_notification("notify_tip")
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        client
+            .notify("notify_tip", (rpc::BlockNumber::from(7),))
+            .unwrap();
+        // the notification is done once it is queued
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while server.requests().is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        // a call with an id would not reach the notification handler
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].0, "notify_tip");
+        assert_eq!(
+            requests[0].1,
+            Params::Array(vec![Value::String("0x7".to_owned())])
+        );
+    }
+
+    #[test]
+    fn decode_peers_state() {
+        let states = serde_json::json!([{
+            "blocks_in_flight": "0x56",
+            "last_updated": "0x16a95af332d",
+            "peer": "0x1"
+        }]);
+        let server = MockServerBuilder::new()
+            .respond("get_peers_state", states)
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let states = client.get_peers_state().unwrap();
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].peer.value(), 1);
+        assert_eq!(states[0].blocks_in_flight.value(), 0x56);
+        assert_eq!(states[0].last_updated.value(), 0x16a95af332d);
+        assert_eq!(server.requests()[0].0, "get_peers_state");
+    }
+
+    #[test]
+    fn decode_lock_hash_index_states() {
+        let lock_hash = fixed::H256([1; 32]);
+        let block_hash = fixed::H256([2; 32]);
+        let states = serde_json::json!([{
+            "lock_hash": lock_hash,
+            "block_number": "0x400",
+            "block_hash": block_hash
+        }]);
+        let server = MockServerBuilder::new()
+            .respond("get_lock_hash_index_states", states)
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let states = client.get_lock_hash_index_states().unwrap();
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].lock_hash, lock_hash);
+        assert_eq!(states[0].block_number.value(), 0x400);
+        assert_eq!(states[0].block_hash, block_hash);
+        assert_eq!(server.requests()[0].0, "get_lock_hash_index_states");
+    }
+
+    #[test]
+    fn index_and_deindex_lock_hash() {
+        let lock_hash = fixed::H256([1; 32]);
+        let state = serde_json::json!({
+            "lock_hash": lock_hash,
+            "block_number": "0x400",
+            "block_hash": fixed::H256([2; 32])
+        });
+        let server = MockServerBuilder::new()
+            .respond("index_lock_hash", state)
+            .respond("deindex_lock_hash", Value::Null)
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let state = client
+            .index_lock_hash(lock_hash.clone(), Some(0x400))
+            .unwrap();
+        assert_eq!(state.lock_hash, lock_hash);
+        assert_eq!(state.block_number.value(), 0x400);
+        client.deindex_lock_hash(lock_hash.clone()).unwrap();
+        let requests = server.requests();
+        let lock_hash = serde_json::to_value(lock_hash).unwrap();
+        assert_eq!(requests[0].0, "index_lock_hash");
+        assert_eq!(
+            requests[0].1,
+            Params::Array(vec![lock_hash.clone(), Value::String("0x400".to_owned())])
+        );
+        assert_eq!(requests[1].0, "deindex_lock_hash");
+        assert_eq!(requests[1].1, Params::Array(vec![lock_hash]));
+    }
+
+    #[test]
+    fn decode_live_cells_by_lock_hash() {
+        let lock_hash = fixed::H256([1; 32]);
+        let tx_hash = fixed::H256([2; 32]);
+        let cells = serde_json::json!([{
+            "created_by": {
+                "block_number": "0x1",
+                "index": "0x0",
+                "tx_hash": tx_hash
+            },
+            "cell_output": {
+                "capacity": "0x2cb6562e4e",
+                "lock": {
+                    "args": "0x",
+                    "code_hash": fixed::H256::default(),
+                    "hash_type": "data"
+                },
+                "type": null
+            },
+            "output_data_len": "0x0",
+            "cellbase": true
+        }]);
+        let server = MockServerBuilder::new()
+            .respond("get_live_cells_by_lock_hash", cells)
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let cells = client
+            .get_live_cells_by_lock_hash(lock_hash.clone(), 2, 50, Some(true))
+            .unwrap();
+        assert_eq!(cells.len(), 1);
+        assert_eq!(cells[0].created_by.tx_hash, tx_hash);
+        assert_eq!(cells[0].created_by.block_number.value(), 1);
+        assert_eq!(cells[0].cell_output.capacity.value(), 0x2cb6562e4e);
+        assert!(cells[0].cellbase);
+        let requests = server.requests();
+        assert_eq!(requests[0].0, "get_live_cells_by_lock_hash");
+        assert_eq!(
+            requests[0].1,
+            Params::Array(vec![
+                serde_json::to_value(lock_hash).unwrap(),
+                Value::String("0x2".to_owned()),
+                Value::String("0x32".to_owned()),
+                Value::Bool(true),
+            ])
+        );
+    }
+
+    #[test]
+    fn decode_transactions_by_lock_hash() {
+        let lock_hash = fixed::H256([1; 32]);
+        let point = |number: &str, index: &str, byte: u8| {
+            serde_json::json!({
+                "block_number": number,
+                "index": index,
+                "tx_hash": fixed::H256([byte; 32])
+            })
+        };
+        let txs = serde_json::json!([
+            { "created_by": point("0x1", "0x0", 2), "consumed_by": point("0x2", "0x1", 3) },
+            { "created_by": point("0x3", "0x2", 4), "consumed_by": null }
+        ]);
+        let server = MockServerBuilder::new()
+            .respond("get_transactions_by_lock_hash", txs)
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let txs = client
+            .get_transactions_by_lock_hash(lock_hash.clone(), 0, 2, None)
+            .unwrap();
+        assert_eq!(txs.len(), 2);
+        assert_eq!(txs[0].created_by.tx_hash, fixed::H256([2; 32]));
+        let consumed_by = txs[0].consumed_by.as_ref().unwrap();
+        assert_eq!(consumed_by.block_number.value(), 2);
+        assert_eq!(consumed_by.index.value(), 1);
+        assert_eq!(txs[1].created_by.index.value(), 2);
+        assert!(txs[1].consumed_by.is_none());
+        let requests = server.requests();
+        assert_eq!(requests[0].0, "get_transactions_by_lock_hash");
+        assert_eq!(
+            requests[0].1,
+            Params::Array(vec![
+                serde_json::to_value(lock_hash).unwrap(),
+                Value::String("0x0".to_owned()),
+                Value::String("0x2".to_owned()),
+                Value::Null,
+            ])
+        );
+    }
+
+    #[test]
+    fn decode_cellbase_output_capacity_details() {
+        let block_hash = fixed::H256([1; 32]);
+        let reward = serde_json::json!({
+            "primary": "0x102b36211d",
+            "proposal_reward": "0x0",
+            "secondary": "0x2b95a53",
+            "total": "0x102e21b770",
+            "tx_fee": "0x0"
+        });
+        let server = MockServerBuilder::new()
+            .respond_seq(
+                "get_cellbase_output_capacity_details",
+                vec![Ok(reward), Ok(Value::Null)],
+            )
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let reward = client
+            .get_cellbase_output_capacity_details(block_hash.clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(reward.primary.value(), 0x102b36211d);
+        assert_eq!(reward.secondary.value(), 0x2b95a53);
+        assert_eq!(reward.total.value(), 0x102e21b770);
+        assert_eq!(reward.tx_fee.value(), 0);
+        assert_eq!(reward.proposal_reward.value(), 0);
+        assert!(client
+            .get_cellbase_output_capacity_details(block_hash.clone())
+            .unwrap()
+            .is_none());
+        let requests = server.requests();
+        assert_eq!(requests[0].0, "get_cellbase_output_capacity_details");
+        let block_hash = serde_json::to_value(block_hash).unwrap();
+        assert_eq!(requests[0].1, Params::Array(vec![block_hash]));
+    }
 }
@@ -6,27 +6,469 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use jsonrpc_core_client::transports::http;
+use std::{
+    fmt,
+    future::Future as StdFuture,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use jsonrpc_core_client::{transports::http, RpcChannel, RpcError, TypedClient};
+use jsonrpc_server_utils::tokio::{
+    prelude::{
+        future::{self, Either, Loop},
+        Future,
+    },
+    timer::{Delay, Timeout},
+};
 use uckb_jsonrpc_core::client::HttpClient as RawHttpClient;
 use url::Url;
 
-use crate::{error::Result, runtime::Runtime};
+use crate::{
+    error::{Error, Result},
+    metrics::{Metrics, NoopMetrics},
+    runtime::Runtime,
+};
 
+mod helpers;
 mod methods;
 
+pub use self::helpers::{CellsSnapshot, SystemCells};
+
+#[derive(Clone)]
 pub(super) struct HttpClient {
+    // Only for logs and debug output, the password is redacted.
+    url: Url,
     client: RawHttpClient,
+    raw: TypedClient,
+    timeout: Option<Duration>,
+    max_attempts: u32,
+    base_delay: Duration,
+    metrics: Arc<dyn Metrics>,
+}
+
+impl fmt::Debug for HttpClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HttpClient")
+            .field("url", &self.url.as_str())
+            .field("timeout", &self.timeout)
+            .field("max_attempts", &self.max_attempts)
+            .field("base_delay", &self.base_delay)
+            .finish()
+    }
+}
+
+fn redact(url: &Url) -> Url {
+    let mut url = url.to_owned();
+    if url.password().is_some() {
+        let _ = url.set_password(Some("***"));
+    }
+    url
 }
 
 impl HttpClient {
     pub(super) fn new(rt: Runtime, url: &Url) -> Result<Self> {
-        log::trace!("initialize a http client to connect {}", url);
-        let fut_conn = http::connect::<RawHttpClient>(url.as_str());
-        let client = rt.block_on_01(fut_conn)?;
-        Ok(Self { client })
+        log::trace!("initialize a http client to connect {}", redact(url));
+        let fut_conn = http::connect::<RpcChannel>(url.as_str());
+        let channel = rt.block_on_01(fut_conn)?;
+        let client = RawHttpClient::from(channel.clone());
+        let raw = TypedClient::from(channel);
+        Ok(Self {
+            url: redact(url),
+            client,
+            raw,
+            timeout: None,
+            max_attempts: 1,
+            base_delay: Duration::from_secs(0),
+            metrics: Arc::new(NoopMetrics),
+        })
+    }
+
+    pub(super) fn with_url(&self, rt: Runtime, url: &Url) -> Result<Self> {
+        let mut client = Self::new(rt, url)?;
+        client.timeout = self.timeout;
+        client.max_attempts = self.max_attempts;
+        client.base_delay = self.base_delay;
+        client.metrics = Arc::clone(&self.metrics);
+        Ok(client)
+    }
+
+    pub(super) fn set_timeout(&mut self, timeout: Option<Duration>) {
+        log::trace!("set the timeout of the http client to {:?}", timeout);
+        self.timeout = timeout;
+    }
+
+    pub(super) fn set_retry(&mut self, max_attempts: u32, base_delay: Duration) {
+        log::trace!(
+            "set the retry of the http client to {} attempts with base delay {:?}",
+            max_attempts,
+            base_delay,
+        );
+        self.max_attempts = max_attempts.max(1);
+        self.base_delay = base_delay;
+    }
+
+    pub(super) fn set_metrics(&mut self, metrics: Arc<dyn Metrics>) {
+        log::trace!("set the metrics of the http client");
+        self.metrics = metrics;
     }
 
     pub(super) fn client(&self) -> RawHttpClient {
         self.client.clone()
     }
+
+    pub(super) fn raw(&self) -> TypedClient {
+        self.raw.clone()
+    }
+
+    fn instrument<M, F>(
+        &self,
+        method: M,
+        future: F,
+    ) -> impl Future<Item = F::Item, Error = F::Error>
+    where
+        M: AsRef<str>,
+        F: Future,
+        F::Error: fmt::Display,
+    {
+        let url = self.url.clone();
+        let metrics = Arc::clone(&self.metrics);
+        let start = Instant::now();
+        log::trace!("http call {} to {}", method.as_ref(), url);
+        metrics.on_call(method.as_ref());
+        future.then(move |result| {
+            let method = method.as_ref();
+            let elapsed = start.elapsed();
+            match result {
+                Ok(_) => {
+                    log::debug!("http call {} to {} done in {:?}", method, url, elapsed);
+                    metrics.on_success(method, elapsed);
+                }
+                Err(ref err) => {
+                    log::warn!(
+                        "http call {} to {} failed in {:?} since {}",
+                        method,
+                        url,
+                        elapsed,
+                        err
+                    );
+                    metrics.on_error(method, elapsed);
+                }
+            }
+            result
+        })
+    }
+
+    fn with_timeout<F>(&self, future: F) -> impl Future<Item = F::Item, Error = Error>
+    where
+        F: Future<Error = RpcError>,
+    {
+        if let Some(timeout) = self.timeout {
+            let fut = Timeout::new(future, timeout).map_err(move |err| {
+                err.into_inner().map(Into::into).unwrap_or_else(|| {
+                    Error::timeout(format!("http request is not done in {:?}", timeout))
+                })
+            });
+            Either::A(fut)
+        } else {
+            Either::B(future.map_err(Into::into))
+        }
+    }
+
+    fn retry<T, F>(&self, mut call: F) -> Result<T>
+    where
+        F: FnMut() -> Result<T>,
+    {
+        let mut attempt = 1;
+        loop {
+            match call() {
+                Err(err) => match self.retry_delay(attempt, &err) {
+                    Some(delay) => thread::sleep(delay),
+                    None => return Err(err),
+                },
+                result => return result,
+            }
+            attempt += 1;
+        }
+    }
+
+    async fn retry_async<T, F, Fut>(&self, mut call: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: StdFuture<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match call().await {
+                Err(err) => match self.retry_delay(attempt, &err) {
+                    Some(delay) => tokio::time::sleep(delay).await,
+                    None => return Err(err),
+                },
+                result => return result,
+            }
+            attempt += 1;
+        }
+    }
+
+    // retry inside a legacy future, so each item of a batch is retried on its own
+    fn retry_01<T, F, Fut>(&self, call: F) -> impl Future<Item = T, Error = Error>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Item = T, Error = Error>,
+    {
+        let client = self.clone();
+        future::loop_fn(1, move |attempt| {
+            let client = client.clone();
+            call().then(move |result| match result {
+                Err(err) => match client.retry_delay(attempt, &err) {
+                    Some(delay) => Either::A(
+                        Delay::new(Instant::now() + delay)
+                            .then(move |_| Ok(Loop::Continue(attempt + 1))),
+                    ),
+                    None => Either::B(future::err(err)),
+                },
+                Ok(item) => Either::B(future::ok(Loop::Break(item))),
+            })
+        })
+    }
+
+    fn retry_delay(&self, attempt: u32, err: &Error) -> Option<Duration> {
+        if attempt >= self.max_attempts || !err.is_transient() {
+            return None;
+        }
+        let factor = 2u32.pow((attempt - 1).min(16));
+        let delay = self.base_delay.saturating_mul(factor);
+        log::debug!(
+            "retry the http request (attempt {}) after {:?}",
+            attempt,
+            delay
+        );
+        Some(delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use jsonrpc_core::Value;
+    use parking_lot::Mutex;
+    use uckb_jsonrpc_core::types::packed;
+
+    use crate::{
+        error::Error,
+        metrics::Metrics,
+        testing::{client, MockServerBuilder},
+    };
+
+    #[test]
+    fn time_out_slow_requests() {
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x1".to_owned()))
+            .delay(Duration::from_millis(500))
+            .start()
+            .unwrap();
+        let (_rt, mut client) = client(server.url());
+        client
+            .set_http_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        let err = client.get_tip_block_number().unwrap_err();
+        assert!(matches!(err, Error::Timeout(_)), "{}", err);
+        assert!(err.is_transient());
+    }
+
+    #[test]
+    fn retry_timed_out_requests() {
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x1".to_owned()))
+            .delay(Duration::from_millis(500))
+            .start()
+            .unwrap();
+        let (_rt, mut client) = client(server.url());
+        client
+            .set_http_timeout(Some(Duration::from_millis(50)))
+            .unwrap()
+            .set_http_retry(2, Duration::from_millis(10))
+            .unwrap();
+        let err = client.get_tip_block_number().unwrap_err();
+        assert!(matches!(err, Error::Timeout(_)), "{}", err);
+        assert_eq!(server.hits(), 2);
+    }
+
+    #[test]
+    fn saturate_retry_delay() {
+        let server = MockServerBuilder::new().start().unwrap();
+        let (_rt, mut client) = client(server.url());
+        let base_delay = Duration::from_secs(u64::MAX / 4);
+        client.set_http_retry(u32::MAX, base_delay).unwrap();
+        let http = client.http().unwrap();
+        let err = Error::timeout("test");
+        let delays = [1, 2, 3, 17, u32::MAX - 1]
+            .iter()
+            .map(|attempt| http.retry_delay(*attempt, &err).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(delays[0], base_delay);
+        assert_eq!(delays[1], base_delay * 2);
+        assert!(delays.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(delays[4], Duration::MAX);
+    }
+
+    #[derive(Default)]
+    struct CountingMetrics {
+        events: Mutex<Vec<(String, &'static str)>>,
+    }
+
+    impl Metrics for CountingMetrics {
+        fn on_call(&self, method: &str) {
+            self.events.lock().push((method.to_owned(), "call"));
+        }
+
+        fn on_success(&self, method: &str, _elapsed: Duration) {
+            self.events.lock().push((method.to_owned(), "success"));
+        }
+
+        fn on_error(&self, method: &str, _elapsed: Duration) {
+            self.events.lock().push((method.to_owned(), "error"));
+        }
+    }
+
+    #[test]
+    fn report_calls_to_metrics() {
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x1".to_owned()))
+            .respond_seq(
+                "get_current_epoch",
+                vec![Err(jsonrpc_core::Error::invalid_params("test"))],
+            )
+            .fail_requests(1)
+            .start()
+            .unwrap();
+        let (_rt, mut client) = client(server.url());
+        let metrics = Arc::new(CountingMetrics::default());
+        client
+            .set_http_metrics(Arc::clone(&metrics) as Arc<dyn Metrics>)
+            .unwrap()
+            .set_http_retry(2, Duration::from_millis(10))
+            .unwrap();
+        assert_eq!(client.get_tip_block_number().unwrap(), 1);
+        assert!(client.get_current_epoch().is_err());
+        let events = metrics.events.lock().clone();
+        let (tip, epoch) = (
+            "get_tip_block_number".to_owned(),
+            "get_current_epoch".to_owned(),
+        );
+        assert_eq!(
+            events,
+            vec![
+                (tip.clone(), "call"),
+                (tip.clone(), "error"),
+                (tip.clone(), "call"),
+                (tip, "success"),
+                (epoch.clone(), "call"),
+                (epoch, "error"),
+            ]
+        );
+    }
+
+    #[test]
+    fn retry_reads_on_transient_errors() {
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x1".to_owned()))
+            .fail_requests(2)
+            .start()
+            .unwrap();
+        let (_rt, mut client) = client(server.url());
+        client.set_http_retry(3, Duration::from_millis(10)).unwrap();
+        assert_eq!(client.get_tip_block_number().unwrap(), 1);
+        assert_eq!(server.hits(), 3);
+    }
+
+    #[test]
+    fn retry_async_reads_on_transient_errors() {
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x1".to_owned()))
+            .fail_requests(2)
+            .start()
+            .unwrap();
+        let (rt, mut client) = client(server.url());
+        client.set_http_retry(3, Duration::from_millis(10)).unwrap();
+        let client = client.to_async().unwrap();
+        assert_eq!(rt.block_on(client.get_tip_block_number()).unwrap(), 1);
+        assert_eq!(server.hits(), 3);
+    }
+
+    #[test]
+    fn do_not_retry_json_rpc_errors() {
+        let server = MockServerBuilder::new()
+            .respond_seq(
+                "get_tip_block_number",
+                vec![
+                    Err(jsonrpc_core::Error::internal_error()),
+                    Ok(Value::String("0x1".to_owned())),
+                ],
+            )
+            .start()
+            .unwrap();
+        let (_rt, mut client) = client(server.url());
+        client.set_http_retry(3, Duration::from_millis(10)).unwrap();
+        assert!(client.get_tip_block_number().is_err());
+        assert_eq!(server.hits(), 1);
+    }
+
+    #[test]
+    fn do_not_retry_send_transaction() {
+        let server = MockServerBuilder::new()
+            .respond(
+                "send_transaction",
+                Value::String(format!("0x{}", "00".repeat(32))),
+            )
+            .fail_requests(2)
+            .start()
+            .unwrap();
+        let (_rt, mut client) = client(server.url());
+        client.set_http_retry(3, Duration::from_millis(10)).unwrap();
+        assert!(client
+            .send_transaction(packed::Transaction::default(), None)
+            .is_err());
+        assert_eq!(server.hits(), 1);
+    }
+
+    #[test]
+    fn redact_password_in_debug() {
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x1".to_owned()))
+            .start()
+            .unwrap();
+        let mut url = server.url().to_owned();
+        url.set_username("user").unwrap();
+        url.set_password(Some("secret")).unwrap();
+        let (_rt, client) = client(&url);
+        let output = format!("{:?}", client);
+        assert!(output.contains("user:***@"), "{}", output);
+        assert!(!output.contains("secret"), "{}", output);
+        assert_eq!(client.get_tip_block_number().unwrap(), 1);
+    }
+
+    #[test]
+    fn clones_work_independently() {
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x1".to_owned()))
+            .start()
+            .unwrap();
+        let other = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x2".to_owned()))
+            .start()
+            .unwrap();
+        let (_rt, mut client) = client(server.url());
+        client.set_http_retry(2, Duration::from_millis(10)).unwrap();
+        let cloned = client.clone();
+        let switched = client.with_http_url(other.url()).unwrap();
+        assert_eq!(client.get_tip_block_number().unwrap(), 1);
+        assert_eq!(cloned.get_tip_block_number().unwrap(), 1);
+        assert_eq!(switched.get_tip_block_number().unwrap(), 2);
+        assert_eq!(server.hits(), 2);
+        assert_eq!(other.hits(), 1);
+        assert!(format!("{:?}", switched).contains("max_attempts: 2"));
+    }
 }
@@ -6,7 +6,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{fmt, net::SocketAddr, sync::Arc, time::Duration};
 
 use jsonrpc_server_utils::tokio::runtime::Runtime as RawRuntime01;
 use parking_lot::RwLock;
@@ -15,18 +15,45 @@ use url::Url;
 
 use crate::{
     error::{Error, Result},
+    metrics::Metrics,
     runtime::Runtime,
 };
 
 mod http;
 mod tcp;
 
+pub use self::http::{CellsSnapshot, SystemCells};
 use self::{http::HttpClient, tcp::TcpClient};
 
+#[derive(Clone)]
 pub struct Client {
     runtime: Runtime,
-    tcp: Option<TcpClient>,
+    tcp: Option<Arc<TcpClient>>,
     http: Option<HttpClient>,
+    system_cells: Arc<RwLock<Option<SystemCells>>>,
+}
+
+#[derive(Clone)]
+pub struct AsyncClient {
+    runtime: Runtime,
+    http: HttpClient,
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("tcp", &self.tcp.is_some())
+            .field("http", &self.http)
+            .finish()
+    }
+}
+
+impl fmt::Debug for AsyncClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AsyncClient")
+            .field("http", &self.http)
+            .finish()
+    }
 }
 
 impl Client {
@@ -36,6 +63,7 @@ impl Client {
             runtime: Runtime::new(rt, rt01),
             tcp: None,
             http: None,
+            system_cells: Arc::new(RwLock::new(None)),
         }
     }
 
@@ -44,17 +72,21 @@ impl Client {
     }
 
     fn tcp(&self) -> Result<&TcpClient> {
-        self.tcp.as_ref().ok_or(Error::NoTcpClient)
+        self.tcp.as_deref().ok_or(Error::NoTcpClient)
     }
 
     fn http(&self) -> Result<&HttpClient> {
         self.http.as_ref().ok_or(Error::NoHttpClient)
     }
 
+    fn http_mut(&mut self) -> Result<&mut HttpClient> {
+        self.http.as_mut().ok_or(Error::NoHttpClient)
+    }
+
     pub fn enable_tcp(&mut self, addr: &SocketAddr) -> Result<&mut Self> {
         log::info!("enable tcp client");
         if self.tcp.is_none() {
-            self.tcp = Some(TcpClient::new(self.runtime(), addr)?);
+            self.tcp = Some(Arc::new(TcpClient::new(self.runtime(), addr)?));
         }
         Ok(self)
     }
@@ -66,4 +98,126 @@ impl Client {
         }
         Ok(self)
     }
+
+    pub fn with_http_url(&self, url: &Url) -> Result<Self> {
+        log::info!("create a client with another http url");
+        let http = self.http()?.with_url(self.runtime(), url)?;
+        Ok(Self {
+            runtime: self.runtime(),
+            tcp: self.tcp.clone(),
+            http: Some(http),
+            system_cells: Arc::new(RwLock::new(None)),
+        })
+    }
+
+    pub fn set_http_timeout(&mut self, timeout: Option<Duration>) -> Result<&mut Self> {
+        log::info!("set http client timeout");
+        self.http_mut()?.set_timeout(timeout);
+        Ok(self)
+    }
+
+    pub fn set_http_retry(&mut self, max_attempts: u32, base_delay: Duration) -> Result<&mut Self> {
+        log::info!("set http client retry");
+        self.http_mut()?.set_retry(max_attempts, base_delay);
+        Ok(self)
+    }
+
+    pub fn set_http_metrics(&mut self, metrics: Arc<dyn Metrics>) -> Result<&mut Self> {
+        log::info!("set http client metrics");
+        self.http_mut()?.set_metrics(metrics);
+        Ok(self)
+    }
+
+    pub fn to_async(&self) -> Result<AsyncClient> {
+        log::info!("create an async client");
+        let runtime = self.runtime();
+        let http = self.http()?.clone();
+        Ok(AsyncClient { runtime, http })
+    }
+}
+
+impl AsyncClient {
+    fn runtime(&self) -> Runtime {
+        self.runtime.clone()
+    }
+
+    fn http(&self) -> &HttpClient {
+        &self.http
+    }
+}
+
+const _: () = {
+    const fn assert<T: Send + Sync>() {}
+    assert::<Client>();
+    assert::<AsyncClient>();
+};
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use jsonrpc_core::Value;
+
+    use crate::{
+        error::Error,
+        testing::{client, MockServerBuilder},
+    };
+
+    #[test]
+    fn share_client_between_threads() {
+        let delay = Duration::from_millis(300);
+        let count = 4;
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x1".to_owned()))
+            .delay(delay)
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let client = Arc::new(client);
+        let start = Instant::now();
+        let handles = (0..count)
+            .map(|_| {
+                let client = Arc::clone(&client);
+                thread::spawn(move || client.get_tip_block_number())
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap().unwrap(), 1);
+        }
+        assert!(start.elapsed() < delay * count);
+        assert_eq!(server.hits(), count as usize);
+    }
+
+    // Call a plain http node over https. Only a tls connector gets past the scheme check,
+    // then it fails in the tls handshake.
+    fn call_over_https() -> Error {
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x1".to_owned()))
+            .start()
+            .unwrap();
+        let mut url = server.url().to_owned();
+        url.set_scheme("https").unwrap();
+        let (_rt, client) = client(&url);
+        client.get_tip_block_number().unwrap_err()
+    }
+
+    #[cfg(not(feature = "tls"))]
+    #[test]
+    fn reject_https_without_tls() {
+        let err = call_over_https();
+        assert!(err.to_string().contains("scheme must be http"), "{}", err);
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn connect_https_through_tls() {
+        let err = call_over_https();
+        let msg = err.to_string();
+        assert!(!msg.contains("scheme must be http"), "{}", msg);
+        assert!(msg.contains("error trying to connect"), "{}", msg);
+    }
 }
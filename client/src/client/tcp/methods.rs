@@ -9,7 +9,7 @@
 use std::result;
 
 use futures::{compat::Stream01CompatExt as _, StreamExt as _};
-use jsonrpc_server_utils::tokio::prelude::IntoFuture as _;
+use jsonrpc_server_utils::tokio::prelude::{Future as _, IntoFuture as _};
 use tokio::sync::mpsc;
 use uckb_jsonrpc_core::types::rpc;
 
@@ -69,11 +69,15 @@ impl TcpClient {
         }
         // setup a new subscription
         log::trace!("tcp subscribe {:?}", topic);
-        let fut_subscribe = self.client().subscribe(topic).into_future();
-        let stream_01 = rt.block_on_01(fut_subscribe).map_err(move |err| {
-            log::error!("failed to subscribe {:?} since {}", topic, err);
-            Error::tcp_client(err)
-        })?;
+        let fut_subscribe = self
+            .client()
+            .subscribe(topic)
+            .into_future()
+            .map_err(move |err| {
+                log::error!("failed to subscribe {:?} since {}", topic, err);
+                Error::tcp_client(err)
+            });
+        let stream_01 = rt.block_on_01(fut_subscribe)?;
         log::trace!("tcp subscribe {:?} is ok", topic);
         let (sender, mut receiver) = mpsc::channel(1);
         let mut stream = stream_01.compat();
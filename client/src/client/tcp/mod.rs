@@ -49,7 +49,7 @@ impl TcpClient {
             log::trace!("successfully connect via {}", stream.local_addr().unwrap());
             stream
         });
-        let stream = rt.block_on_01(fut_conn).map_err(Error::tcp_client)?;
+        let stream = rt.block_on_01(fut_conn.map_err(Error::tcp_client))?;
         let (sink, stream) = StreamCodec::stream_incoming().framed(stream).split();
         let sink = sink.sink_map_err(|e| RpcError::Other(e.into()));
         let stream = stream.map_err(|e| RpcError::Other(e.into()));
@@ -17,6 +17,9 @@ pub enum Error {
     #[error("runtime error: {0}")]
     Runtime(String),
 
+    #[error("timeout error: {0}")]
+    Timeout(String),
+
     #[error("client error: TCP client is not activated")]
     NoTcpClient,
     #[error("client error: HTTP client is not activated")]
@@ -27,12 +30,34 @@ pub enum Error {
     #[error("http client error: {0}")]
     HttpClient(String),
 
+    #[error("address error: {0}")]
+    Address(String),
+
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("overflow: {0}")]
+    Overflow(String),
+    #[error("decode error: {0}")]
+    Decode(String),
+    #[error("{} of the calls failed: {}", .0.len(), display_failures(.0))]
+    Failures(Vec<(String, Error)>),
+
     #[error("rpc error: {0}")]
     RpcError(cli::RpcError),
 }
 
 pub type Result<T> = result::Result<T, Error>;
 
+fn display_failures(failures: &[(String, Error)]) -> String {
+    failures
+        .iter()
+        .map(|(call, err)| format!("{}: {}", call, err))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 impl From<cli::RpcError> for Error {
     fn from(error: cli::RpcError) -> Self {
         Self::RpcError(error)
@@ -50,6 +75,10 @@ impl Error {
         Self::Runtime(inner.to_string())
     }
 
+    pub fn timeout<T: fmt::Display>(inner: T) -> Self {
+        Self::Timeout(inner.to_string())
+    }
+
     pub fn tcp_client<T: fmt::Display>(inner: T) -> Self {
         Self::TcpClient(inner.to_string())
     }
@@ -58,6 +87,33 @@ impl Error {
         Self::HttpClient(inner.to_string())
     }
 
+    pub fn address<T: fmt::Display>(inner: T) -> Self {
+        Self::Address(inner.to_string())
+    }
+
+    pub fn invalid_argument<T: fmt::Display>(inner: T) -> Self {
+        Self::InvalidArgument(inner.to_string())
+    }
+
+    pub fn not_found<T: fmt::Display>(inner: T) -> Self {
+        Self::NotFound(inner.to_string())
+    }
+
+    pub fn overflow<T: fmt::Display>(inner: T) -> Self {
+        Self::Overflow(inner.to_string())
+    }
+
+    pub fn decode<T: fmt::Display>(inner: T) -> Self {
+        Self::Decode(inner.to_string())
+    }
+
+    pub(crate) fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::Timeout(_) | Self::RpcError(cli::RpcError::Other(_))
+        )
+    }
+
     pub fn rpc_invalid_params<T: fmt::Display>(inner: T) -> Self {
         rpc::Error {
             code: rpc::ErrorCode::InvalidParams,
@@ -9,9 +9,14 @@
 pub extern crate uckb_jsonrpc_core as core;
 pub extern crate url;
 
+pub mod address;
 pub mod error;
+pub mod metrics;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod traits;
 
 mod client;
 mod runtime;
 
-pub use client::Client;
+pub use client::{AsyncClient, CellsSnapshot, Client, SystemCells};
@@ -0,0 +1,20 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::time::Duration;
+
+pub trait Metrics: Send + Sync {
+    fn on_call(&self, _method: &str) {}
+    fn on_success(&self, _method: &str, _elapsed: Duration) {}
+    fn on_error(&self, _method: &str, _elapsed: Duration) {}
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {}
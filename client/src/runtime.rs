@@ -6,11 +6,16 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::{future::Future, result, sync::Arc};
+use std::{
+    future::Future,
+    sync::{mpsc, Arc},
+};
 
 use jsonrpc_server_utils::tokio::{prelude::Future as Future01, runtime::Runtime as RawRuntime01};
 use parking_lot::RwLock;
-use tokio::{runtime::Runtime as RawRuntime, task::JoinHandle};
+use tokio::{runtime::Runtime as RawRuntime, sync::oneshot, task::JoinHandle};
+
+use crate::error::{Error, Result};
 
 #[derive(Clone)]
 pub(crate) struct Runtime {
@@ -43,14 +48,19 @@ impl Runtime {
         self.core.spawn(future)
     }
 
-    pub(crate) fn block_on_01<F>(&self, future: F) -> result::Result<F::Item, F::Error>
+    pub(crate) fn block_on_01<F>(&self, future: F) -> Result<F::Item>
     where
         F: Future01 + Send + 'static,
         F::Item: Send + 'static,
-        F::Error: Send + 'static,
+        F::Error: Into<Error> + Send + 'static,
     {
         log::trace!("block on a legacy future");
-        self.legacy_support.write().block_on(future)
+        let (sender, receiver) = mpsc::channel();
+        self.spawn_01(future.then(move |result| {
+            let _result = sender.send(result.map_err(Into::into));
+            Ok(())
+        }));
+        receiver.recv().map_err(Error::runtime)?
     }
 
     pub(crate) fn spawn_01<F>(&self, future: F)
@@ -60,6 +70,20 @@ impl Runtime {
         F::Error: Send,
     {
         log::trace!("spawn a legacy future");
-        self.legacy_support.write().spawn(future);
+        self.legacy_support.read().executor().spawn(future);
+    }
+
+    pub(crate) fn run_01<F>(&self, future: F) -> impl Future<Output = Result<F::Item>>
+    where
+        F: Future01<Error = Error> + Send + 'static,
+        F::Item: Send + 'static,
+    {
+        log::trace!("run a legacy future");
+        let (sender, receiver) = oneshot::channel();
+        self.spawn_01(future.then(move |result| {
+            let _result = sender.send(result);
+            Ok(())
+        }));
+        async move { receiver.await.map_err(Error::runtime)? }
     }
 }
@@ -0,0 +1,292 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A mock CKB node which serves canned responses over HTTP.
+
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use jsonrpc_core::{
+    futures::{
+        future::{self, Either},
+        Future as _,
+    },
+    IoHandler, Params, Value,
+};
+use jsonrpc_http_server::{hyper, RequestMiddlewareAction, Response, Server, ServerBuilder};
+use jsonrpc_server_utils::tokio::timer::Delay;
+use parking_lot::Mutex;
+use url::Url;
+
+use crate::error::{Error, Result};
+
+type Requests = Arc<Mutex<Vec<(String, Params)>>>;
+type Responder = Arc<dyn Fn(Params) -> jsonrpc_core::Result<Value> + Send + Sync>;
+
+#[derive(Default)]
+pub struct MockServerBuilder {
+    responders: Vec<(String, Responder)>,
+    notifications: Vec<String>,
+    delay: Option<Duration>,
+    failures: usize,
+}
+
+pub struct MockServer {
+    server: Server,
+    url: Url,
+    requests: Requests,
+    hits: Arc<AtomicUsize>,
+}
+
+impl MockServerBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Responds `result` to every call of `method`.
+    pub fn respond(self, method: &str, result: Value) -> Self {
+        self.respond_with(method, move |_| Ok(result.clone()))
+    }
+
+    /// Responds `results` in order to the calls of `method`.
+    ///
+    /// The last result is repeated once the others are used up.
+    pub fn respond_seq(self, method: &str, results: Vec<jsonrpc_core::Result<Value>>) -> Self {
+        assert!(!results.is_empty(), "results should not be empty");
+        let next = AtomicUsize::new(0);
+        self.respond_with(method, move |_| {
+            let index = next.fetch_add(1, Ordering::SeqCst);
+            results[index.min(results.len() - 1)].clone()
+        })
+    }
+
+    /// Responds the output of `func` to every call of `method`.
+    pub fn respond_with<F>(mut self, method: &str, func: F) -> Self
+    where
+        F: Fn(Params) -> jsonrpc_core::Result<Value> + Send + Sync + 'static,
+    {
+        self.responders.push((method.to_owned(), Arc::new(func)));
+        self
+    }
+
+    /// Accepts notifications of `method`.
+    ///
+    /// Only a request without an id is a notification, a call of `method` fails.
+    pub fn accept_notification(mut self, method: &str) -> Self {
+        self.notifications.push(method.to_owned());
+        self
+    }
+
+    /// Delays every response without blocking the server.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Answers the first `count` HTTP requests with status 503.
+    ///
+    /// These requests never reach a method, so they are not recorded.
+    pub fn fail_requests(mut self, count: usize) -> Self {
+        self.failures = count;
+        self
+    }
+
+    /// Starts the server on a random local port.
+    pub fn start(self) -> Result<MockServer> {
+        let requests = Requests::default();
+        let mut handler = IoHandler::new();
+        for (name, responder) in self.responders {
+            let requests = Arc::clone(&requests);
+            let delay = self.delay;
+            handler.add_method(&name.clone(), move |params: Params| {
+                log::trace!("mock server receives {}", name);
+                requests.lock().push((name.clone(), params.clone()));
+                let result = responder(params);
+                if let Some(delay) = delay {
+                    Either::A(Delay::new(Instant::now() + delay).then(move |_| result))
+                } else {
+                    Either::B(future::result(result))
+                }
+            });
+        }
+        for name in self.notifications {
+            let requests = Arc::clone(&requests);
+            handler.add_notification(&name.clone(), move |params: Params| {
+                log::trace!("mock server receives notification {}", name);
+                requests.lock().push((name.clone(), params));
+            });
+        }
+        let hits = Arc::new(AtomicUsize::new(0));
+        let middleware = {
+            let hits = Arc::clone(&hits);
+            let failures = self.failures;
+            move |request: hyper::Request<hyper::Body>| -> RequestMiddlewareAction {
+                if hits.fetch_add(1, Ordering::SeqCst) < failures {
+                    log::trace!("mock server fails a request");
+                    Response::service_unavailable("mock failure").into()
+                } else {
+                    request.into()
+                }
+            }
+        };
+        let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+        let server = ServerBuilder::new(handler)
+            .request_middleware(middleware)
+            .start_http(&addr)
+            .map_err(Error::http_client)?;
+        let url =
+            Url::parse(&format!("http://{}", server.address())).map_err(Error::http_client)?;
+        log::trace!("mock server listens on {}", url);
+        Ok(MockServer {
+            server,
+            url,
+            requests,
+            hits,
+        })
+    }
+}
+
+impl MockServer {
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Returns the method names and params of all received calls and notifications, in order.
+    pub fn requests(&self) -> Vec<(String, Params)> {
+        self.requests.lock().clone()
+    }
+
+    /// Returns the count of all received HTTP requests, including the failed ones.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::SeqCst)
+    }
+
+    pub fn close(self) {
+        self.server.close();
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn client(url: &Url) -> (Arc<tokio::runtime::Runtime>, crate::Client) {
+    use jsonrpc_server_utils::tokio::runtime::Runtime as RawRuntime01;
+    use parking_lot::RwLock;
+
+    let rt = Arc::new(tokio::runtime::Runtime::new().unwrap());
+    let rt01 = Arc::new(RwLock::new(RawRuntime01::new().unwrap()));
+    let mut client = crate::Client::new(Arc::clone(&rt), rt01);
+    client.enable_http(url).unwrap();
+    (rt, client)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use jsonrpc_core::{serde_json, Params, Value};
+    use uckb_jsonrpc_core::types::{core, fixed, prelude::*, rpc};
+
+    use super::{client, MockServerBuilder};
+    use crate::error::Error;
+
+    #[test]
+    fn respond_tip_block_number() {
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x3039".to_owned()))
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        assert_eq!(client.get_tip_block_number().unwrap(), 12345);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].0, "get_tip_block_number");
+    }
+
+    #[test]
+    fn respond_block() {
+        let block = core::BlockBuilder::default().build();
+        let json = serde_json::to_value(rpc::BlockView::from(block.clone())).unwrap();
+        let server = MockServerBuilder::new()
+            .respond("get_block", json)
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let hash: fixed::H256 = block.hash().unpack();
+        let fetched = client.get_block(hash.clone(), None).unwrap();
+        assert_eq!(fetched.unwrap().hash(), block.hash());
+        let requests = server.requests();
+        assert_eq!(requests[0].0, "get_block");
+        let hash = serde_json::to_value(hash).unwrap();
+        assert_eq!(requests[0].1, Params::Array(vec![hash, Value::Null]));
+    }
+
+    #[test]
+    fn respond_sequence_with_errors() {
+        let server = MockServerBuilder::new()
+            .respond_seq(
+                "get_tip_block_number",
+                vec![
+                    Err(jsonrpc_core::Error::invalid_params("first")),
+                    Ok(Value::String("0x1".to_owned())),
+                ],
+            )
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let err = client.get_tip_block_number().unwrap_err();
+        assert!(err.to_string().contains("first"), "{}", err);
+        assert_eq!(client.get_tip_block_number().unwrap(), 1);
+        assert_eq!(client.get_tip_block_number().unwrap(), 1);
+    }
+
+    #[test]
+    fn fail_requests() {
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x1".to_owned()))
+            .fail_requests(1)
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let err = client.get_tip_block_number().unwrap_err();
+        assert!(matches!(err, Error::RpcError(_)), "{}", err);
+        assert_eq!(client.get_tip_block_number().unwrap(), 1);
+        assert_eq!(server.hits(), 2);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn delay_responses() {
+        let delay = Duration::from_millis(200);
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", Value::String("0x1".to_owned()))
+            .delay(delay)
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let start = Instant::now();
+        assert_eq!(client.get_tip_block_number().unwrap(), 1);
+        assert!(start.elapsed() >= delay);
+    }
+
+    #[test]
+    fn reject_calls_of_notifications() {
+        let server = MockServerBuilder::new()
+            .accept_notification("notify_tip")
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        let err = client.call::<_, Value>("notify_tip", ()).unwrap_err();
+        assert!(matches!(err, Error::RpcError(_)), "{}", err);
+        assert!(server.requests().is_empty());
+    }
+}
@@ -0,0 +1,263 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A trait over the read-only chain queries, so they can be faked in tests.
+
+use crate::{
+    core::types::{core, fixed, packed, rpc},
+    error::Result,
+    Client,
+};
+
+pub trait CkbRpc {
+    fn get_block(
+        &self,
+        block_hash: fixed::H256,
+        verbosity: Option<u32>,
+    ) -> Result<Option<core::BlockView>>;
+    fn get_block_by_number(
+        &self,
+        block_number: core::BlockNumber,
+        verbosity: Option<u32>,
+    ) -> Result<Option<core::BlockView>>;
+    fn get_header(
+        &self,
+        block_hash: fixed::H256,
+        verbosity: Option<u32>,
+    ) -> Result<Option<core::HeaderView>>;
+    fn get_header_by_number(
+        &self,
+        block_number: core::BlockNumber,
+        verbosity: Option<u32>,
+    ) -> Result<Option<core::HeaderView>>;
+    fn get_transaction(&self, tx_hash: fixed::H256) -> Result<Option<rpc::TransactionWithStatus>>;
+    fn get_block_hash(&self, block_number: core::BlockNumber) -> Result<Option<fixed::H256>>;
+    fn get_tip_header(&self, verbosity: Option<u32>) -> Result<core::HeaderView>;
+    fn get_tip_block_number(&self) -> Result<core::BlockNumber>;
+    fn get_live_cell(
+        &self,
+        out_point: packed::OutPoint,
+        with_data: bool,
+    ) -> Result<rpc::CellWithStatus>;
+}
+
+impl CkbRpc for Client {
+    fn get_block(
+        &self,
+        block_hash: fixed::H256,
+        verbosity: Option<u32>,
+    ) -> Result<Option<core::BlockView>> {
+        Client::get_block(self, block_hash, verbosity)
+    }
+
+    fn get_block_by_number(
+        &self,
+        block_number: core::BlockNumber,
+        verbosity: Option<u32>,
+    ) -> Result<Option<core::BlockView>> {
+        Client::get_block_by_number(self, block_number, verbosity)
+    }
+
+    fn get_header(
+        &self,
+        block_hash: fixed::H256,
+        verbosity: Option<u32>,
+    ) -> Result<Option<core::HeaderView>> {
+        Client::get_header(self, block_hash, verbosity)
+    }
+
+    fn get_header_by_number(
+        &self,
+        block_number: core::BlockNumber,
+        verbosity: Option<u32>,
+    ) -> Result<Option<core::HeaderView>> {
+        Client::get_header_by_number(self, block_number, verbosity)
+    }
+
+    fn get_transaction(&self, tx_hash: fixed::H256) -> Result<Option<rpc::TransactionWithStatus>> {
+        Client::get_transaction(self, tx_hash)
+    }
+
+    fn get_block_hash(&self, block_number: core::BlockNumber) -> Result<Option<fixed::H256>> {
+        Client::get_block_hash(self, block_number)
+    }
+
+    fn get_tip_header(&self, verbosity: Option<u32>) -> Result<core::HeaderView> {
+        Client::get_tip_header(self, verbosity)
+    }
+
+    fn get_tip_block_number(&self) -> Result<core::BlockNumber> {
+        Client::get_tip_block_number(self)
+    }
+
+    fn get_live_cell(
+        &self,
+        out_point: packed::OutPoint,
+        with_data: bool,
+    ) -> Result<rpc::CellWithStatus> {
+        Client::get_live_cell(self, out_point, with_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        core::types::{core, fixed, packed, prelude::*, rpc},
+        error::{Error, Result},
+        testing::{client, MockServerBuilder},
+    };
+
+    use super::CkbRpc;
+
+    #[derive(Default)]
+    struct FakeChain {
+        headers: Vec<core::HeaderView>,
+        transactions: HashMap<fixed::H256, rpc::TransactionWithStatus>,
+    }
+
+    impl FakeChain {
+        fn with_length(length: u64) -> Self {
+            let headers = (0..length)
+                .map(|number| core::HeaderBuilder::default().number(number.pack()).build())
+                .collect();
+            Self {
+                headers,
+                ..Default::default()
+            }
+        }
+
+        fn block(&self, number: core::BlockNumber) -> Option<core::BlockView> {
+            self.headers.get(number as usize).map(|header| {
+                core::BlockBuilder::default()
+                    .header(header.clone())
+                    .build_unchecked()
+            })
+        }
+    }
+
+    impl CkbRpc for FakeChain {
+        fn get_block(
+            &self,
+            block_hash: fixed::H256,
+            _verbosity: Option<u32>,
+        ) -> Result<Option<core::BlockView>> {
+            Ok(self
+                .headers
+                .iter()
+                .position(|header| header.hash() == block_hash.pack())
+                .and_then(|number| self.block(number as u64)))
+        }
+
+        fn get_block_by_number(
+            &self,
+            block_number: core::BlockNumber,
+            _verbosity: Option<u32>,
+        ) -> Result<Option<core::BlockView>> {
+            Ok(self.block(block_number))
+        }
+
+        fn get_header(
+            &self,
+            block_hash: fixed::H256,
+            _verbosity: Option<u32>,
+        ) -> Result<Option<core::HeaderView>> {
+            Ok(self
+                .headers
+                .iter()
+                .find(|header| header.hash() == block_hash.pack())
+                .cloned())
+        }
+
+        fn get_header_by_number(
+            &self,
+            block_number: core::BlockNumber,
+            _verbosity: Option<u32>,
+        ) -> Result<Option<core::HeaderView>> {
+            Ok(self.headers.get(block_number as usize).cloned())
+        }
+
+        fn get_transaction(
+            &self,
+            tx_hash: fixed::H256,
+        ) -> Result<Option<rpc::TransactionWithStatus>> {
+            Ok(self.transactions.get(&tx_hash).cloned())
+        }
+
+        fn get_block_hash(&self, block_number: core::BlockNumber) -> Result<Option<fixed::H256>> {
+            Ok(self
+                .headers
+                .get(block_number as usize)
+                .map(|header| header.hash().unpack()))
+        }
+
+        fn get_tip_header(&self, _verbosity: Option<u32>) -> Result<core::HeaderView> {
+            self.headers
+                .last()
+                .cloned()
+                .ok_or_else(|| Error::not_found("the tip header"))
+        }
+
+        fn get_tip_block_number(&self) -> Result<core::BlockNumber> {
+            self.get_tip_header(None).map(|header| header.number())
+        }
+
+        fn get_live_cell(
+            &self,
+            _out_point: packed::OutPoint,
+            _with_data: bool,
+        ) -> Result<rpc::CellWithStatus> {
+            Err(Error::not_found("the live cell"))
+        }
+    }
+
+    // Collect the hashes of the latest `count` blocks, from the tip down.
+    fn latest_block_hashes<C: CkbRpc>(chain: &C, count: u64) -> Result<Vec<fixed::H256>> {
+        let tip = chain.get_tip_block_number()?;
+        (0..count.min(tip + 1))
+            .map(|offset| {
+                let number = tip - offset;
+                chain
+                    .get_block_hash(number)?
+                    .ok_or_else(|| Error::not_found(format!("the hash of block {}", number)))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn drive_generic_logic_with_a_fake() {
+        let chain = FakeChain::with_length(5);
+        let hashes = latest_block_hashes(&chain, 3).unwrap();
+        let expected = (2..5)
+            .rev()
+            .map(|number| chain.headers[number].hash().unpack())
+            .collect::<Vec<fixed::H256>>();
+        assert_eq!(hashes, expected);
+        let block = chain.get_block(expected[0].clone(), None).unwrap().unwrap();
+        assert_eq!(block.number(), 4);
+        assert!(latest_block_hashes(&FakeChain::default(), 1).is_err());
+    }
+
+    #[test]
+    fn inherent_methods_still_resolve_with_the_trait_in_scope() {
+        let hash = fixed::H256([1; 32]);
+        let server = MockServerBuilder::new()
+            .respond("get_tip_block_number", "0x7".into())
+            .respond(
+                "get_block_hash",
+                jsonrpc_core::serde_json::to_value(hash.clone()).unwrap(),
+            )
+            .respond("get_block", jsonrpc_core::Value::Null)
+            .start()
+            .unwrap();
+        let (_rt, client) = client(server.url());
+        assert!(client.get_block(hash.clone(), None).unwrap().is_none());
+        assert_eq!(latest_block_hashes(&client, 1).unwrap(), vec![hash],);
+    }
+}
@@ -61,6 +61,13 @@ pub(crate) mod http {
         fn get_block_hash(&self, block_number: BlockNumber) -> Result<Option<H256>>;
         #[rpc(name = "get_tip_header")]
         fn get_tip_header(&self, verbosity: Option<Uint32>) -> Result<HeaderView>;
+        #[rpc(name = "get_cells_by_lock_hash")]
+        fn get_cells_by_lock_hash(
+            &self,
+            lock_hash: H256,
+            from: BlockNumber,
+            to: BlockNumber,
+        ) -> Result<Vec<CellOutputWithOutPoint>>;
         #[rpc(name = "get_live_cell")]
         fn get_live_cell(&self, out_point: OutPoint, with_data: bool) -> Result<CellWithStatus>;
         #[rpc(name = "get_tip_block_number")]
@@ -71,6 +78,11 @@ pub(crate) mod http {
         fn get_epoch_by_number(&self, epoch_number: EpochNumber) -> Result<Option<EpochView>>;
         #[rpc(name = "get_block_economic_state")]
         fn get_block_economic_state(&self, block_hash: H256) -> Result<Option<BlockEconomicState>>;
+        #[rpc(name = "get_cellbase_output_capacity_details")]
+        fn get_cellbase_output_capacity_details(
+            &self,
+            block_hash: H256,
+        ) -> Result<Option<BlockReward>>;
         #[rpc(name = "get_transaction_proof")]
         fn get_transaction_proof(
             &self,
@@ -85,6 +97,8 @@ pub(crate) mod http {
             block_hash: H256,
             verbosity: Option<Uint32>,
         ) -> Result<Option<BlockView>>;
+        #[rpc(name = "get_block_median_time")]
+        fn get_block_median_time(&self, block_hash: H256) -> Result<Option<Timestamp>>;
         // Module Pool
         #[rpc(name = "send_transaction")]
         fn send_transaction(
@@ -114,6 +128,8 @@ pub(crate) mod http {
         fn local_node_info(&self) -> Result<LocalNode>;
         #[rpc(name = "get_peers")]
         fn get_peers(&self) -> Result<Vec<RemoteNode>>;
+        #[rpc(name = "get_peers_state")]
+        fn get_peers_state(&self) -> Result<Vec<PeerState>>;
         #[rpc(name = "get_banned_addresses")]
         fn get_banned_addresses(&self) -> Result<Vec<BannedAddr>>;
         #[rpc(name = "clear_banned_addresses")]
@@ -160,6 +176,33 @@ pub(crate) mod http {
             name: String,
             config_opt: Option<ExtraLoggerConfig>,
         ) -> Result<()>;
+        // Module Indexer
+        #[rpc(name = "get_live_cells_by_lock_hash")]
+        fn get_live_cells_by_lock_hash(
+            &self,
+            lock_hash: H256,
+            page: Uint64,
+            per_page: Uint64,
+            reverse_order: Option<bool>,
+        ) -> Result<Vec<LiveCell>>;
+        #[rpc(name = "get_transactions_by_lock_hash")]
+        fn get_transactions_by_lock_hash(
+            &self,
+            lock_hash: H256,
+            page: Uint64,
+            per_page: Uint64,
+            reverse_order: Option<bool>,
+        ) -> Result<Vec<CellTransaction>>;
+        #[rpc(name = "get_lock_hash_index_states")]
+        fn get_lock_hash_index_states(&self) -> Result<Vec<LockHashIndexState>>;
+        #[rpc(name = "index_lock_hash")]
+        fn index_lock_hash(
+            &self,
+            lock_hash: H256,
+            index_from: Option<BlockNumber>,
+        ) -> Result<LockHashIndexState>;
+        #[rpc(name = "deindex_lock_hash")]
+        fn deindex_lock_hash(&self, lock_hash: H256) -> Result<()>;
         // Module IntegrationTest
         #[rpc(name = "process_block_without_verify")]
         fn process_block_without_verify(